@@ -0,0 +1,45 @@
+//! Compares the generation of a `CompiledModel` with the generation of a `Model`.
+//! 
+//! Run with `cargo bench`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use svggen::{Argument, Model};
+
+/// The number of images generated by each measure.
+const ITERATIONS: u32 = 20_000;
+
+/// Returns the time taken to generate the image `ITERATIONS` times.
+fn measure<F: FnMut() -> usize>(mut generate: F) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(generate());
+    }
+    start.elapsed()
+}
+
+fn main() {
+    // A model with many small texts between the references, as in real templates
+    let mut source = String::from("<svg xmlns=\"http://www.w3.org/2000/svg\">\n#SET fill = 2\n");
+    for i in 0..50 {
+        source.push_str(&format!("<rect x=\"#GET 0\" y=\"{i}\" width=\"#GET 1\" height=\"1\" fill=\"#VAR fill\"/>\n"));
+    }
+    source.push_str("#IF 3\n<title>#GET 3</title>\n#END\n#FOR 4\n<text>#ITEM</text>\n#END\n</svg>\n");
+    let model: Model = source.parse().unwrap();
+    let compiled = model.precompiled();
+
+    let args = [
+        Argument::number(10.0),
+        Argument::number(2.5),
+        Argument::from("red"),
+        Argument::from("Chart"),
+        Argument::List((0..10).map(Argument::from).collect()),
+    ];
+    assert_eq!(compiled.generate(&args).unwrap(), model.generate(&args).unwrap());
+
+    let model_time = measure(|| model.generate(&args).unwrap().len());
+    let compiled_time = measure(|| compiled.generate(&args).unwrap().len());
+    println!("Model::generate          {:>10.2?} per image", model_time / ITERATIONS);
+    println!("CompiledModel::generate  {:>10.2?} per image", compiled_time / ITERATIONS);
+    println!("speedup                  {:>10.2}x", model_time.as_secs_f64() / compiled_time.as_secs_f64());
+}
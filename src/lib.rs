@@ -1,14 +1,86 @@
+#![no_std]
+
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use core::cell::{Cell, OnceCell};
+use core::hash::{Hash, Hasher};
+use core::ops::Range;
+use core::str::FromStr;
+use core::{error, fmt};
+#[cfg(feature = "std")]
+use alloc::borrow::ToOwned;
+#[cfg(feature = "std")]
+use std::collections::{HashMap, HashSet};
+#[cfg(feature = "std")]
 use std::io::{self, BufRead};
+#[cfg(feature = "std")]
+use std::path::{Path, PathBuf};
+#[cfg(feature = "std")]
+use std::sync::OnceLock;
+#[cfg(feature = "std")]
+use std::fs;
+#[cfg(feature = "std")]
 use rutil::read::*;
 
+/// The map used internally by models: a `HashMap` with the `std` feature, a `BTreeMap` without it.
+#[cfg(feature = "std")]
+type Map<K, V> = HashMap<K, V>;
+/// The map used internally by models: a `HashMap` with the `std` feature, a `BTreeMap` without it.
+#[cfg(not(feature = "std"))]
+type Map<K, V> = BTreeMap<K, V>;
+
+/// The set used internally by models: a `HashSet` with the `std` feature, a `BTreeSet` without it.
+#[cfg(feature = "std")]
+type Set<T> = HashSet<T>;
+/// The set used internally by models: a `HashSet` with the `std` feature, a `BTreeSet` without it.
+#[cfg(not(feature = "std"))]
+type Set<T> = BTreeSet<T>;
+
+/// The cell caching the analyses of a model: a `OnceLock` with the `std` feature, a `OnceCell`
+/// without it (models are then not `Sync`).
+#[cfg(feature = "std")]
+type Cache<T> = OnceLock<T>;
+/// The cell caching the analyses of a model: a `OnceLock` with the `std` feature, a `OnceCell`
+/// without it (models are then not `Sync`).
+#[cfg(not(feature = "std"))]
+type Cache<T> = OnceCell<T>;
+
 // ========================= //
 // ========= IMAGE ========= //
 // ========================= //
 
 /// An image (in svg format)
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// 
+/// With the `serde` feature, an image is serialized as its content in bytes.
+/// 
+/// # Examples
+/// 
+/// ```
+/// # #[cfg(feature = "serde")] {
+/// use svggen::Image;
+/// 
+/// let image = Image::from("<svg/>".as_bytes());
+/// 
+/// let json = serde_json::to_string(&image).unwrap();
+/// assert_eq!(serde_json::from_str::<Image>(&json).unwrap(), image);
+/// 
+/// let bytes = bincode::serialize(&image).unwrap();
+/// assert_eq!(bincode::deserialize::<Image>(&bytes).unwrap(), image);
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Image {
     /// The content of the image.
+    #[cfg_attr(feature = "serde", serde(with = "serde_bytes"))]
     content: Box<[u8]>,
 }
 
@@ -26,14 +98,8 @@ impl Image {
     pub fn content(&self) -> &[u8] {
         &self.content
     }
-}
 
-impl<T: Into<Box<[u8]>>> From<T> for Image {
-    /// Creates a new image from the given content.
-    /// 
-    /// # Arguments
-    /// 
-    /// * `content` - The content of the image.
+    /// Returns a copy of the content of the image.
     /// 
     /// # Examples
     /// 
@@ -41,337 +107,8847 @@ impl<T: Into<Box<[u8]>>> From<T> for Image {
     /// use svggen::Image;
     /// 
     /// let image = Image::from("Hello World!".as_bytes());
+    /// let mut bytes = image.to_bytes();
+    /// bytes.extend_from_slice(b"!!");
+    /// 
+    /// assert_eq!(bytes, b"Hello World!!!");
     /// assert_eq!(image.content(), b"Hello World!");
     /// ```
-    fn from(content: T) -> Self {
-        Image { content: content.into() }
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.content.to_vec()
     }
-}
-
-impl Readable for Image {
-    /// There is no parsing error. The content is not parsed.
-    type ParseError = ();
 
-    /// Creates a new image from a reader.
-    /// 
-    /// # Arguments
-    /// 
-    /// * `reader` - The reader to read the image from.
+    /// Returns the content of the image, without copying it.
     /// 
     /// # Examples
     /// 
     /// ```
-    /// use rutil::read::Readable;
     /// use svggen::Image;
     /// 
-    /// let mut data = "Hello World!".as_bytes();
+    /// let image = Image::from("Hello World!".as_bytes());
+    /// let address = image.content().as_ptr();
     /// 
-    /// // data implements `io::Read` so we can use it as a reader
-    /// let image = Image::load(&mut data).unwrap();
-    /// assert_eq!(image.content(), b"Hello World!");
+    /// let bytes = image.into_bytes();
+    /// assert_eq!(bytes, b"Hello World!");
+    /// assert_eq!(bytes.as_ptr(), address);
     /// ```
-    fn load<R: std::io::Read>(reader: &mut R) -> Result<Self, ReadError<Self::ParseError>> {
-        let mut content = Vec::new();
-        reader.read_to_end(&mut content)?;
-        Ok(Image { content: content.into() })
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.content.into_vec()
     }
-}
-
-// ========================= //
-// ======= MODEL PART ====== //
-// ========================= //
-
-/// A model part used to create a model.
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub enum ModelPart {
-    /// Some text.
-    Text(Box<[u8]>),
-
-    /// An argument.
-    Argument(usize),
-}
 
-impl<T: Into<Box<[u8]>>> From<T> for ModelPart {
-    /// Creates a new text model part from the given content.
+    /// Returns the length of the content of the image, in bytes.
     /// 
-    /// # Arguments
+    /// # Examples
     /// 
-    /// * `content` - The content of the text model part.
+    /// ```
+    /// use svggen::Image;
+    /// 
+    /// let image = Image::from("Hello World!".as_bytes());
+    /// assert_eq!(image.len(), 12);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.content.len()
+    }
+
+    /// Returns `true` if the content of the image is empty.
     /// 
     /// # Examples
     /// 
     /// ```
-    /// use svggen::ModelPart;
+    /// use svggen::Image;
     /// 
-    /// let part = ModelPart::from("Hello World!".as_bytes());
-    /// assert_eq!(part, ModelPart::Text(b"Hello World!".to_vec().into()));
+    /// assert!(Image::from(Vec::new()).is_empty());
+    /// assert!(!Image::from("Hello World!".as_bytes()).is_empty());
     /// ```
-    fn from(content: T) -> Self {
-        ModelPart::Text(content.into())
+    pub fn is_empty(&self) -> bool {
+        self.content.is_empty()
     }
-}
-
-// ========================= //
-// ===== MODEL ARGUMENT ==== //
-// ========================= //
-
-/// A model argument used to pass arguments to a model to generate an image.
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub enum Argument<'a> {
-    /// Some text.
-    Text(Box<[u8]>),
 
-    /// An image.
-    Image(&'a Image),
-
-    /// An empty argument.
-    Empty,
-}
-
-impl<T: Into<Box<[u8]>>> From<T> for Argument<'static> {
-    /// Creates a new text argument from the given content.
+    /// Returns the content of the image as a string, without copying it.
     /// 
-    /// # Arguments
+    /// # Examples
     /// 
-    /// * `content` - The content of the text argument.
+    /// ```
+    /// use svggen::Image;
     /// 
-    /// # Examples
+    /// let image = Image::from("<svg/>".as_bytes());
+    /// let text = image.as_str().unwrap();
     /// 
+    /// assert_eq!(text, "<svg/>");
+    /// assert_eq!(text.as_ptr(), image.content().as_ptr());
     /// ```
-    /// use svggen::Argument;
     /// 
-    /// let arg = Argument::from("Hello World!".as_bytes());
-    /// assert_eq!(arg, Argument::Text(b"Hello World!".to_vec().into()));
+    /// # Errors
+    /// 
+    /// * `Utf8Error` - The content of the image is not valid UTF-8.
+    /// 
     /// ```
-    fn from(content: T) -> Self {
-        Argument::Text(content.into())
+    /// use svggen::Image;
+    /// 
+    /// let image = Image::from(vec![0x3c, 0xff, 0x3e]);
+    /// assert!(image.as_str().is_err());
+    /// ```
+    pub fn as_str(&self) -> Result<&str, core::str::Utf8Error> {
+        core::str::from_utf8(&self.content)
     }
-}
-
-// ========================= //
-// ========= MODEL ========= //
-// ========================= //
-
-/// A model used to generate images.
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Model {
-    /// The parts of the model.
-    parts: Box<[ModelPart]>,
-}
 
-impl Model {
-    /// Returns the parts of the model.
+    /// Returns the content of the image as a string, replacing invalid UTF-8 by `U+FFFD`.
+    /// 
+    /// The content is only copied if it is not valid UTF-8.
     /// 
     /// # Examples
     /// 
     /// ```
-    /// use svggen::{Model, ModelPart};
+    /// use svggen::Image;
     /// 
-    /// let model = Model::from(vec![
-    ///     ModelPart::from("Hello ".as_bytes()),
-    ///     ModelPart::Argument(0),
-    ///     ModelPart::from("!".as_bytes()),
-    /// ]);
+    /// let image = Image::from("<svg/>".as_bytes());
+    /// assert_eq!(image.to_string_lossy(), "<svg/>");
     /// 
-    /// assert_eq!(model.parts(), &[
-    ///     ModelPart::Text(b"Hello ".to_vec().into()),
-    ///     ModelPart::Argument(0),
-    ///     ModelPart::Text(b"!".to_vec().into()),
-    /// ]);
+    /// let image = Image::from(vec![0x3c, 0xff, 0x3e]);
+    /// assert_eq!(image.to_string_lossy(), "<\u{FFFD}>");
     /// ```
-    pub fn parts(&self) -> &[ModelPart] {
-        &self.parts
+    pub fn to_string_lossy(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.content)
     }
 
-    /// Write the model to a writer.
+    /// Creates a new image by joining the contents of the given images.
+    /// 
+    /// The contents are joined end to end, without separator.
     /// 
     /// # Arguments
     /// 
-    /// * `writer` - The writer to write the model to.
-    /// * `args` - The arguments to use.
+    /// * `images` - The images to join.
     /// 
     /// # Examples
     /// 
     /// ```
-    /// use rutil::read::Readable;
-    /// use svggen::{Model, ModelPart, Image, Argument};
+    /// use svggen::Image;
     /// 
-    /// let model = Model::from(vec![
-    ///     ModelPart::from("Hello ".as_bytes()),
-    ///     ModelPart::Argument(0),
-    ///     ModelPart::from("!".as_bytes()),
-    /// ]);
+    /// let circle = Image::from("<circle r=\"1\"/>".as_bytes());
+    /// let rect = Image::from("<rect/>".as_bytes());
     /// 
-    /// let image = Image::from("World".as_bytes());
-    /// let args = [Argument::Image(&image)];
+    /// let image = Image::concat(&[&circle, &rect]);
+    /// assert_eq!(image.len(), circle.len() + rect.len());
+    /// assert_eq!(image.content(), b"<circle r=\"1\"/><rect/>");
+    /// ```
+    pub fn concat(images: &[&Image]) -> Image {
+        let content: Vec<u8> = images.iter().flat_map(|image| image.content.iter().copied()).collect();
+        Image { content: content.into() }
+    }
+
+    /// Wraps the image in a root `<svg>` element of the given size, so composed
+    /// fragments form a standalone document.
     /// 
-    /// let mut buffer: Vec<u8> = Vec::new();
+    /// The root element has the SVG namespace, the width and the height, and a
+    /// `viewBox` of `0 0 width height`. An image that is already an SVG (see
+    /// `Image::is_svg`) is returned unchanged.
     /// 
-    /// // buffer implements `io::Write` so we can use it as a writer
-    /// model.write(&mut buffer, &args).unwrap();
+    /// # Arguments
+    /// 
+    /// * `width` - The width of the root element.
+    /// * `height` - The height of the root element.
+    /// 
+    /// # Examples
     /// 
-    /// assert_eq!(buffer, b"Hello World!");
     /// ```
-    pub fn write<W: io::Write>(&self, writer: &mut W, args: &[Argument]) -> io::Result<()> {
-        for part in self.parts.iter() {
-            match part {
-                ModelPart::Text(content) => writer.write_all(content)?,
-                ModelPart::Argument(index) => match args.get(*index) {
-                    Some(Argument::Text(content)) => writer.write_all(content)?,
-                    Some(Argument::Image(image)) => writer.write_all(image.content())?,
-                    Some(Argument::Empty) => (),
-                    None => return Err(io::Error::new(
-                        io::ErrorKind::InvalidInput,
-                        format!("Missing argument: {}", index),
-                    )),
-                },
-            }
+    /// use svggen::Image;
+    /// 
+    /// let circle = Image::from("<circle r=\"1\"/>".as_bytes());
+    /// let rect = Image::from("<rect/>".as_bytes());
+    /// 
+    /// let image = Image::concat(&[&circle, &rect]).wrap(24.0, 12.5);
+    /// assert!(image.is_svg());
+    /// assert_eq!(image.dimensions(), Some((24.0, 12.5)));
+    /// assert_eq!(
+    ///     image.as_str().unwrap(),
+    ///     "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"24\" height=\"12.5\" viewBox=\"0 0 24 12.5\"><circle r=\"1\"/><rect/></svg>",
+    /// );
+    /// 
+    /// // An image with a root `<svg>` is not wrapped again
+    /// assert_eq!(image.wrap(100.0, 100.0), image);
+    /// ```
+    pub fn wrap(&self, width: f64, height: f64) -> Image {
+        if self.is_svg() {
+            return self.clone();
         }
-        Ok(())
+        let (width, height) = (NumberFmt::new(width), NumberFmt::new(height));
+        let mut content = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">",
+            width, height, width, height,
+        ).into_bytes();
+        content.extend_from_slice(&self.content);
+        content.extend_from_slice(b"</svg>");
+        Image { content: content.into() }
     }
 
-    /// Creates an image from the model.
+    /// Replaces every occurrence of some bytes in the image, from left to right.
+    /// 
+    /// This is a naive byte substitution, without any knowledge of XML: the bytes
+    /// are also replaced in tag names, attribute names, comments... An empty `needle`
+    /// never matches.
     /// 
     /// # Arguments
     /// 
-    /// * `args` - The arguments to use.
+    /// * `needle` - The bytes to replace.
+    /// * `replacement` - The bytes written instead.
     /// 
     /// # Examples
     /// 
     /// ```
-    /// use rutil::read::Readable;
-    /// use svggen::{Model, ModelPart, Image, Argument};
-    /// 
-    /// let model = Model::from(vec![
-    ///     ModelPart::from("Hello ".as_bytes()),
-    ///     ModelPart::Argument(0),
-    ///     ModelPart::from("!".as_bytes()),
-    /// ]);
+    /// use svggen::Image;
     /// 
-    /// let image = Image::from("World".as_bytes());
-    /// let args = [Argument::Image(&image)];
+    /// let image = Image::from("<rect fill=\"#f00\"/><circle fill=\"#f00\"/>".as_bytes());
     /// 
-    /// let image = model.generate(&args).unwrap();
+    /// let recolored = image.replace(b"#f00", b"#00f");
+    /// assert_eq!(recolored.content(), b"<rect fill=\"#00f\"/><circle fill=\"#00f\"/>");
     /// 
-    /// assert_eq!(image.content(), b"Hello World!");
+    /// let image = Image::from("<rect/><circle/>".as_bytes());
+    /// assert_eq!(image.replace(b"rect", b"ellipse").content(), b"<ellipse/><circle/>");
+    /// assert_eq!(image.replace(b"path", b"line"), image);
+    /// assert_eq!(image.replace(b"", b"x"), image);
     /// ```
-    pub fn generate(&self, args: &[Argument]) -> Result<Image, usize> {
-        use std::io::Write;
-        let mut buffer = Vec::with_capacity(1024);
-        for part in self.parts.iter() {
-            match part {
-                ModelPart::Text(content) => buffer.write_all(content).unwrap(),
-                ModelPart::Argument(index) => match args.get(*index) {
-                    Some(Argument::Text(content)) => buffer.write_all(content).unwrap(),
-                    Some(Argument::Image(image)) => buffer.write_all(image.content()).unwrap(),
-                    Some(Argument::Empty) => (),
-                    None => return Err(*index),
-                },
-            }
+    pub fn replace(&self, needle: &[u8], replacement: &[u8]) -> Image {
+        if needle.is_empty() {
+            return self.clone();
         }
-        Ok(Image { content: buffer.into() })
+        let mut content = Vec::with_capacity(self.content.len());
+        let mut rest = &self.content[..];
+        while let Some(position) = find(rest, needle) {
+            content.extend_from_slice(&rest[..position]);
+            content.extend_from_slice(replacement);
+            rest = &rest[position + needle.len()..];
+        }
+        content.extend_from_slice(rest);
+        Image { content: content.into() }
     }
-}
 
-impl<T: Into<Box<[ModelPart]>>> From<T> for Model {
-    /// Creates a new model from the given parts.
+    /// Write the image to a writer.
+    /// 
+    /// Requires the `std` feature.
     /// 
     /// # Arguments
     /// 
-    /// * `parts` - The parts of the model.
+    /// * `writer` - The writer to write the image to.
     /// 
     /// # Examples
     /// 
     /// ```
-    /// use svggen::{Model, ModelPart};
+    /// use svggen::Image;
     /// 
-    /// let model = Model::from(vec![
-    ///     ModelPart::from("Hello ".as_bytes()),
-    ///     ModelPart::Argument(0),
-    ///     ModelPart::from("!".as_bytes()),
-    /// ]);
+    /// let image = Image::from("Hello World!".as_bytes());
     /// 
-    /// assert_eq!(model.parts(), &[
-    ///     ModelPart::Text(b"Hello ".to_vec().into()),
-    ///     ModelPart::Argument(0),
-    ///     ModelPart::Text(b"!".to_vec().into()),
-    /// ]);
+    /// let mut buffer: Vec<u8> = Vec::new();
+    /// image.write(&mut buffer).unwrap();
+    /// 
+    /// assert_eq!(buffer, b"Hello World!");
     /// ```
-    fn from(parts: T) -> Self {
-        Model { parts: parts.into() }
+    #[cfg(feature = "std")]
+    pub fn write<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.content)
     }
-}
-
-impl Readable for Model {
-    /// There is no parsing error.
-    type ParseError = ();
 
-    /// Creates a new model from a reader.
+    /// Saves the image to a file.
+    /// 
+    /// The file is created if it does not exist, and truncated if it does.
+    /// 
+    /// Requires the `std` feature.
     /// 
     /// # Arguments
     /// 
-    /// * `reader` - The reader to read the model from.
+    /// * `path` - The path of the file to save the image to.
     /// 
     /// # Examples
     /// 
     /// ```
-    /// use rutil::read::Readable;
-    /// use svggen::{Model, ModelPart, Image, Argument};
+    /// use std::fs;
+    /// use svggen::Image;
     /// 
-    /// let mut data = "<svg>\n#GET 0\n</svg>".as_bytes();
+    /// let path = std::env::temp_dir().join("svggen_image_save.svg");
+    /// fs::write(&path, "Some longer previous content").unwrap();
     /// 
-    /// // data implements `io::Read` so we can use it as a reader
-    /// let model = Model::load(&mut data).unwrap();
+    /// let image = Image::from("<svg/>".as_bytes());
+    /// image.save(&path).unwrap();
     /// 
-    /// assert_eq!(model.parts(), &[
-    ///     ModelPart::Text(b"<svg>\n".to_vec().into()),
-    ///     ModelPart::Argument(0),
-    ///     ModelPart::Text(b"\n</svg>".to_vec().into()),
-    /// ]);
+    /// assert_eq!(fs::read(&path).unwrap(), b"<svg/>");
+    /// # fs::remove_file(&path).unwrap();
     /// ```
-    fn load<R: std::io::Read>(reader: &mut R) -> Result<Self, ReadError<Self::ParseError>> {
-        let mut buffer: Vec<u8> = Vec::with_capacity(1024);
-        let mut parts: Vec<ModelPart> = Vec::with_capacity(20);
-        
-        // For each line
-        let lines = io::BufReader::new(reader).lines();
-        let mut first_line = true;
-        for line in lines {
-            let line = line?;
-
-            // If the line is an argument reference
-            if line.starts_with("#GET ") {
-                if let Ok(index) = line[5..].parse::<usize>() {
-                    // Add the text buffer to the parts (if it's not empty)
-                    if buffer.len() > 0 {
-                        buffer.push(b'\n');
-                        parts.push(ModelPart::Text(buffer.clone().into()));
-                        buffer.clear();
-                    }
+    #[cfg(feature = "std")]
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, &self.content)
+    }
 
-                    // Add the argument reference to the parts
-                    parts.push(ModelPart::Argument(index));
-                    continue;
+    /// Returns a base64 data URI of the image.
+    /// 
+    /// The result can be used in `<img src="...">` or `url(...)`.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::Image;
+    /// 
+    /// let image = Image::from("<svg/>".as_bytes());
+    /// assert_eq!(image.to_data_uri(), "data:image/svg+xml;base64,PHN2Zy8+");
+    /// 
+    /// // The encoding is padded when needed
+    /// assert_eq!(Image::from("<svg />".as_bytes()).to_data_uri(), "data:image/svg+xml;base64,PHN2ZyAvPg==");
+    /// assert_eq!(Image::from("<svg  />".as_bytes()).to_data_uri(), "data:image/svg+xml;base64,PHN2ZyAgLz4=");
+    /// ```
+    pub fn to_data_uri(&self) -> String {
+        const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let mut uri = String::with_capacity(26 + self.content.len().div_ceil(3) * 4);
+        uri.push_str("data:image/svg+xml;base64,");
+        for chunk in self.content.chunks(3) {
+            let bytes = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+            let group = (bytes[0] as usize) << 16 | (bytes[1] as usize) << 8 | bytes[2] as usize;
+            for i in 0..4 {
+                if i <= chunk.len() {
+                    uri.push(ALPHABET[group >> (18 - 6 * i) & 0x3F] as char);
+                } else {
+                    uri.push('=');
                 }
             }
+        }
+        uri
+    }
 
-            // Add new line if it's not the first line
-            if first_line {
-                first_line = false;
+    /// Returns a percent-encoded data URI of the image.
+    /// 
+    /// Only the characters that are not allowed in a URI (or that would break
+    /// a quoted `src` attribute or `url(...)`) are encoded, so the result is
+    /// usually smaller than `Image::to_data_uri`.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::Image;
+    /// 
+    /// let image = Image::from("<svg fill=\"#fff\"/>".as_bytes());
+    /// assert_eq!(image.to_data_uri_utf8(), "data:image/svg+xml;charset=utf-8,%3Csvg%20fill=%22%23fff%22/%3E");
+    /// ```
+    pub fn to_data_uri_utf8(&self) -> String {
+        const HEX: &[u8; 16] = b"0123456789ABCDEF";
+        let mut uri = String::with_capacity(32 + self.content.len());
+        uri.push_str("data:image/svg+xml;charset=utf-8,");
+        for &byte in self.content.iter() {
+            if byte.is_ascii_alphanumeric() || b"-._~!$&()*+,;=:@/?".contains(&byte) {
+                uri.push(byte as char);
             } else {
-                buffer.push(b'\n');
+                uri.push('%');
+                uri.push(HEX[(byte >> 4) as usize] as char);
+                uri.push(HEX[(byte & 0xF) as usize] as char);
             }
+        }
+        uri
+    }
+
+    /// Returns the width and height of the image.
+    /// 
+    /// The `width` and `height` attributes of the root `<svg>` tag are used when
+    /// they are unitless or in `px`. Otherwise, the size of the `viewBox` is used.
+    /// Returns `None` if the image has no `<svg>` root or no usable size.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::Image;
+    /// 
+    /// let image = Image::from(r#"<svg width="100" height="50px"></svg>"#.as_bytes());
+    /// assert_eq!(image.dimensions(), Some((100.0, 50.0)));
+    /// 
+    /// let image = Image::from(r#"<?xml version="1.0"?><svg viewBox="0 0 200 100"/>"#.as_bytes());
+    /// assert_eq!(image.dimensions(), Some((200.0, 100.0)));
+    /// 
+    /// let image = Image::from(r#"<svg width="100%" height="4.5" viewBox="0,0,200,100"/>"#.as_bytes());
+    /// assert_eq!(image.dimensions(), Some((200.0, 4.5)));
+    /// 
+    /// let image = Image::from(r#"<!-- A > B --><svg data-x='>' hidden width = "3" height="4"/>"#.as_bytes());
+    /// assert_eq!(image.dimensions(), Some((3.0, 4.0)));
+    /// 
+    /// let image = Image::from(r#"<svg xmlns="http://www.w3.org/2000/svg"/>"#.as_bytes());
+    /// assert_eq!(image.dimensions(), None);
+    /// 
+    /// let image = Image::from(r#"<html width="100" height="50"/>"#.as_bytes());
+    /// assert_eq!(image.dimensions(), None);
+    /// ```
+    pub fn dimensions(&self) -> Option<(f64, f64)> {
+        let tag = svg_root_tag(&self.content)?;
+        let view_box = tag_attribute(tag, b"viewBox").and_then(|view_box| {
+            let numbers: Vec<f64> = core::str::from_utf8(view_box).ok()?
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|number| !number.is_empty())
+                .map(str::parse)
+                .collect::<Result<_, _>>().ok()?;
+            match numbers[..] {
+                [_, _, width, height] => Some((width, height)),
+                _ => None,
+            }
+        });
+        let length = |name: &[u8]| {
+            let value = core::str::from_utf8(tag_attribute(tag, name)?).ok()?.trim();
+            value.strip_suffix("px").unwrap_or(value).trim_end().parse::<f64>().ok()
+        };
+        let width = length(b"width").or(view_box.map(|(width, _)| width))?;
+        let height = length(b"height").or(view_box.map(|(_, height)| height))?;
+        Some((width, height))
+    }
+
+    /// Returns the kind of content of the image, sniffed from its first bytes.
+    /// 
+    /// A UTF-8 byte order mark and whitespace are skipped. The content is markup if it
+    /// then starts with `<`, and an SVG if its root tag (after the XML declaration,
+    /// comments and doctype) is an `<svg>` tag. The rest of the content is not checked,
+    /// use `Image::load_validated` for a stricter check.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Image, ImageKind};
+    /// 
+    /// let image = Image::from("\u{feff}\n<?xml version=\"1.0\"?>\n<svg width=\"10\"/>".as_bytes());
+    /// assert_eq!(image.kind(), ImageKind::Svg);
+    /// 
+    /// let image = Image::from("<?xml version=\"1.0\"?>\n<html></html>".as_bytes());
+    /// assert_eq!(image.kind(), ImageKind::Xml);
+    /// 
+    /// let image = Image::from(b"\x89PNG\r\n\x1a\n<svg/>".as_slice());
+    /// assert_eq!(image.kind(), ImageKind::Unknown);
+    /// ```
+    pub fn kind(&self) -> ImageKind {
+        let content = self.content.strip_prefix(b"\xef\xbb\xbf").unwrap_or(&self.content).trim_ascii_start();
+        if !content.starts_with(b"<") {
+            ImageKind::Unknown
+        } else if svg_root_tag(content).is_some() {
+            ImageKind::Svg
+        } else {
+            ImageKind::Xml
+        }
+    }
+
+    /// Returns `true` if the image looks like an SVG (see `Image::kind`).
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::Image;
+    /// 
+    /// assert!(Image::from("<svg></svg>".as_bytes()).is_svg());
+    /// assert!(!Image::from("<?xml version=\"1.0\"?><note/>".as_bytes()).is_svg());
+    /// assert!(!Image::from(b"\x89PNG\r\n\x1a\n".as_slice()).is_svg());
+    /// ```
+    pub fn is_svg(&self) -> bool {
+        self.kind() == ImageKind::Svg
+    }
+
+    /// Creates a new image from a reader, checking that it is a well-formed SVG.
+    /// 
+    /// This is a minimal check: the tags must be balanced, there must be a single
+    /// root element and it must be an `<svg>` element. Entities, namespaces and
+    /// attributes are not checked. Use `Image::load` to skip the check.
+    /// 
+    /// Requires the `std` feature.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `reader` - The reader to read the image from.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use rutil::read::ReadError;
+    /// use svggen::{Image, ImageError};
+    /// 
+    /// let mut data = r#"<?xml version="1.0"?><svg><g><rect/></g><!-- <g> --></svg>"#.as_bytes();
+    /// assert!(Image::load_validated(&mut data).is_ok());
+    /// 
+    /// let mut data = "<svg><g></svg>".as_bytes();
+    /// assert!(matches!(
+    ///     Image::load_validated(&mut data),
+    ///     Err(ReadError::Parse(ImageError::MismatchedTag { .. })),
+    /// ));
+    /// 
+    /// let mut data = "<svg><g>".as_bytes();
+    /// assert!(matches!(
+    ///     Image::load_validated(&mut data),
+    ///     Err(ReadError::Parse(ImageError::UnclosedTag { .. })),
+    /// ));
+    /// 
+    /// let mut data = "<html></html>".as_bytes();
+    /// assert!(matches!(
+    ///     Image::load_validated(&mut data),
+    ///     Err(ReadError::Parse(ImageError::NotSvg { .. })),
+    /// ));
+    /// 
+    /// let mut data = "<svg></svg><svg></svg>".as_bytes();
+    /// assert!(matches!(
+    ///     Image::load_validated(&mut data),
+    ///     Err(ReadError::Parse(ImageError::MultipleRoots)),
+    /// ));
+    /// ```
+    /// 
+    /// # Errors
+    /// 
+    /// * `ReadError::Io` - An IO error occurred.
+    /// * `ReadError::Parse` - The image is not a well-formed SVG.
+    #[cfg(feature = "std")]
+    pub fn load_validated<R: io::Read>(reader: &mut R) -> Result<Self, ReadError<ImageError>> {
+        let mut content = Vec::new();
+        reader.read_to_end(&mut content)?;
+        check_svg(&content).map_err(ReadError::Parse)?;
+        Ok(Image { content: content.into() })
+    }
+
+    /// Creates a new image from a reader, reading at most `max_bytes` bytes.
+    /// 
+    /// This is `Image::load` for untrusted sources (such as user uploads): the reader
+    /// is never read past the limit, so a huge stream cannot exhaust the memory.
+    /// 
+    /// Requires the `std` feature.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `reader` - The reader to read the image from.
+    /// * `max_bytes` - The maximum size of the image.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use std::io::Read;
+    /// use rutil::read::ReadError;
+    /// use svggen::{Image, ImageError};
+    /// 
+    /// let image = Image::load_limited(&mut "<svg/>".as_bytes(), 6).unwrap();
+    /// assert_eq!(image.content(), b"<svg/>");
+    /// 
+    /// // An endless stream of spaces
+    /// let mut reader = std::io::repeat(b' ');
+    /// assert!(matches!(
+    ///     Image::load_limited(&mut reader, 1024),
+    ///     Err(ReadError::Parse(ImageError::TooLarge { max_bytes: 1024 })),
+    /// ));
+    /// 
+    /// let mut reader = "<svg></svg>".as_bytes();
+    /// assert!(Image::load_limited(&mut reader, 10).is_err());
+    /// ```
+    /// 
+    /// # Errors
+    /// 
+    /// * `ReadError::Io` - An IO error occurred.
+    /// * `ReadError::Parse(ImageError::TooLarge)` - The image is larger than `max_bytes`.
+    #[cfg(feature = "std")]
+    pub fn load_limited<R: io::Read>(reader: &mut R, max_bytes: usize) -> Result<Self, ReadError<ImageError>> {
+        use std::io::Read;
+        let mut content = Vec::new();
+        reader.take((max_bytes as u64).saturating_add(1)).read_to_end(&mut content)?;
+        if content.len() > max_bytes {
+            return Err(ReadError::Parse(ImageError::TooLarge { max_bytes }));
+        }
+        Ok(Image { content: content.into() })
+    }
+
+    /// Returns a minified copy of the image.
+    /// 
+    /// The minification is conservative and does not change how the image renders:
+    /// 
+    /// * XML comments are removed.
+    /// * `<metadata>` elements are removed.
+    /// * The `<?xml ...?>` declaration is removed, unless it declares an encoding other than UTF-8.
+    /// * Whitespace-only text between tags is removed, except inside the elements
+    ///   where it can be significant (`<text>`, `<tspan>`, `<textPath>`, `<style>`,
+    ///   `<script>`, `<title>` and `<desc>`).
+    /// 
+    /// Attribute values, character data and non-whitespace text are never modified.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::Image;
+    /// 
+    /// let image = Image::from(r#"<?xml version="1.0" encoding="UTF-8"?>
+    /// <!-- Generator: Some Design Tool -->
+    /// <svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+    ///     <metadata>
+    ///         <rdf:RDF/>
+    ///     </metadata>
+    ///     <g fill="red">
+    ///         <rect x="0" y="0" width="10" height="10" data-note="a  <!-- b -->"/>
+    ///     </g>
+    ///     <text x="0" y="50"><tspan>Hello</tspan> <tspan>World</tspan></text>
+    /// </svg>
+    /// "#.as_bytes());
+    /// 
+    /// assert_eq!(
+    ///     std::str::from_utf8(image.minify().content()).unwrap(),
+    ///     concat!(
+    ///         r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">"#,
+    ///         r#"<g fill="red"><rect x="0" y="0" width="10" height="10" data-note="a  <!-- b -->"/></g>"#,
+    ///         r#"<text x="0" y="50"><tspan>Hello</tspan> <tspan>World</tspan></text>"#,
+    ///         r#"</svg>"#,
+    ///     ),
+    /// );
+    /// ```
+    pub fn minify(&self) -> Image {
+        Image { content: minify_svg(&self.content).into() }
+    }
+
+    /// Returns a copy of the image reindented for reading.
+    /// 
+    /// Every tag, comment and declaration is written on its own line, indented by
+    /// two spaces for each enclosing element. The whitespace between the tags is
+    /// replaced, but an element containing text or character data (and every
+    /// `<text>`, `<style>`, `<script>`...) is written as is on a single line, so
+    /// text content and attribute values are never modified.
+    /// 
+    /// This is meant for inspecting images: it is best-effort and only gives a
+    /// sensible result for well-formed content.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::Image;
+    /// 
+    /// let image = Image::from(r#"<svg>  <g fill="a  b"><rect/>
+    ///         <g><circle r="1"/></g></g><text x="0"> Hello <tspan>World</tspan></text></svg>"#.as_bytes());
+    /// 
+    /// assert_eq!(
+    ///     std::str::from_utf8(image.pretty().content()).unwrap(),
+    ///     concat!(
+    ///         "<svg>\n",
+    ///         "  <g fill=\"a  b\">\n",
+    ///         "    <rect/>\n",
+    ///         "    <g>\n",
+    ///         "      <circle r=\"1\"/>\n",
+    ///         "    </g>\n",
+    ///         "  </g>\n",
+    ///         "  <text x=\"0\"> Hello <tspan>World</tspan></text>\n",
+    ///         "</svg>\n",
+    ///     ),
+    /// );
+    /// ```
+    pub fn pretty(&self) -> Image {
+        Image { content: pretty_svg(&self.content).into() }
+    }
+
+    /// Creates a new image from a reader, decompressing it if it is gzip-compressed (`.svgz`).
+    /// 
+    /// The content is decompressed if it starts with the gzip magic bytes `1f 8b`.
+    /// Otherwise, it is kept unchanged as with `Image::load`.
+    /// 
+    /// Requires the `svgz` feature.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `reader` - The reader to read the image from.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::Image;
+    /// 
+    /// let image = Image::from("<svg/>".as_bytes());
+    /// let compressed = image.to_svgz();
+    /// assert_eq!(&compressed[..2], &[0x1f, 0x8b]);
+    /// 
+    /// assert_eq!(Image::load_auto(&mut compressed.as_slice()).unwrap(), image);
+    /// 
+    /// // Plain images are kept unchanged
+    /// assert_eq!(Image::load_auto(&mut "<svg/>".as_bytes()).unwrap(), image);
+    /// ```
+    /// 
+    /// # Errors
+    /// 
+    /// * `io::Error` - An IO error occurred, or the compressed content is invalid.
+    #[cfg(feature = "svgz")]
+    pub fn load_auto<R: io::Read>(reader: &mut R) -> io::Result<Self> {
+        use std::io::Read;
+        let mut content = Vec::new();
+        reader.read_to_end(&mut content)?;
+        if content.starts_with(&[0x1f, 0x8b]) {
+            let mut decompressed = Vec::with_capacity(content.len() * 4);
+            flate2::read::GzDecoder::new(content.as_slice()).read_to_end(&mut decompressed)?;
+            content = decompressed;
+        }
+        Ok(Image { content: content.into() })
+    }
+
+    /// Returns the content of the image, compressed with gzip (`.svgz`).
+    /// 
+    /// Requires the `svgz` feature.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::Image;
+    /// 
+    /// let image = Image::from("<svg/>".as_bytes());
+    /// assert_eq!(Image::load_auto(&mut image.to_svgz().as_slice()).unwrap(), image);
+    /// ```
+    #[cfg(feature = "svgz")]
+    pub fn to_svgz(&self) -> Vec<u8> {
+        use std::io::Write;
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&self.content).expect("writing to a vector cannot fail");
+        encoder.finish().expect("writing to a vector cannot fail")
+    }
+
+    /// Creates a new image from an asynchronous reader, as `Image::load` does.
+    /// 
+    /// Requires the `tokio` feature.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `reader` - The reader to read the image from.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::Image;
+    /// 
+    /// let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    /// let image = runtime.block_on(async {
+    ///     let mut reader = tokio::io::BufReader::new("<svg/>".as_bytes());
+    ///     Image::load_async(&mut reader).await
+    /// }).unwrap();
+    /// 
+    /// assert_eq!(image.content(), b"<svg/>");
+    /// ```
+    /// 
+    /// # Errors
+    /// 
+    /// * `ReadError::Io` - An IO error occurred while reading.
+    #[cfg(feature = "tokio")]
+    pub async fn load_async<R: tokio::io::AsyncRead + Unpin>(reader: &mut R) -> Result<Self, ReadError<()>> {
+        use tokio::io::AsyncReadExt;
+        let mut content = Vec::new();
+        reader.read_to_end(&mut content).await?;
+        Ok(Image { content: content.into() })
+    }
+
+    /// Loads an image from a memory-mapped file, without copying its content.
+    /// 
+    /// Requires the `memmap2` feature.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `path` - The path of the file to map.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, Argument};
+    /// 
+    /// let path = std::env::temp_dir().join("svggen_image_load_mmap.svg");
+    /// std::fs::write(&path, "<circle r=\"1\"/>").unwrap();
+    /// 
+    /// // SAFETY: the file is not modified while it is mapped
+    /// let image = unsafe { svggen::Image::load_mmap(&path) }.unwrap();
+    /// assert_eq!(image.content(), b"<circle r=\"1\"/>");
+    /// 
+    /// // The content is given as an argument without being copied
+    /// let model: Model = "<g>#GET 0</g>".parse().unwrap();
+    /// let generated = model.generate(&[Argument::Lazy(&image)]).unwrap();
+    /// assert_eq!(generated.content(), b"<g><circle r=\"1\"/></g>");
+    /// 
+    /// drop(image);
+    /// std::fs::remove_file(&path).unwrap();
+    /// ```
+    /// 
+    /// # Errors
+    /// 
+    /// * `io::Error` - An IO error occurred while opening or mapping the file.
+    /// 
+    /// # Safety
+    /// 
+    /// The file must not be modified or truncated, by this process or another one,
+    /// while the returned `MappedImage` exists: the content would change under it,
+    /// and reading a truncated mapping is undefined behavior (see `memmap2::Mmap::map`).
+    #[cfg(feature = "memmap2")]
+    pub unsafe fn load_mmap<P: AsRef<Path>>(path: P) -> io::Result<MappedImage> {
+        let file = fs::File::open(path)?;
+        // SAFETY: the caller guarantees that the file is not modified while mapped
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+        Ok(MappedImage { map })
+    }
+}
+
+/// Returns the attributes of the root tag of the given content, if it is an `<svg>` tag.
+/// 
+/// The XML declaration, processing instructions, comments and doctype before
+/// the root tag are skipped.
+fn svg_root_tag(content: &[u8]) -> Option<&[u8]> {
+    let mut rest = content;
+    loop {
+        let start = rest.iter().position(|c| *c == b'<')?;
+        rest = &rest[start..];
+        let end = if rest.starts_with(b"<?") {
+            find(rest, b"?>")? + 2
+        } else if rest.starts_with(b"<!--") {
+            find(rest, b"-->")? + 3
+        } else if rest.starts_with(b"<!") {
+            find(rest, b">")? + 1
+        } else {
+            break;
+        };
+        rest = &rest[end..];
+    }
+
+    // Check that the root tag is an `<svg>` tag
+    let tag = rest.strip_prefix(b"<svg")?;
+    if !matches!(tag.first(), Some(c) if c.is_ascii_whitespace() || *c == b'>' || *c == b'/') {
+        return None;
+    }
+
+    // Find the end of the tag
+    Some(&tag[..tag_end(tag)?])
+}
+
+/// Returns the value of an attribute in the given tag attributes.
+fn tag_attribute<'t>(tag: &'t [u8], name: &[u8]) -> Option<&'t [u8]> {
+    let mut rest = tag;
+    loop {
+        // Read the name of the attribute
+        rest = &rest[rest.iter().position(|c| !c.is_ascii_whitespace() && *c != b'/')?..];
+        let name_len = rest.iter().position(|c| c.is_ascii_whitespace() || *c == b'=').unwrap_or(rest.len());
+        let attribute = &rest[..name_len];
+        rest = &rest[name_len..];
+
+        // Read the value of the attribute (an attribute without value is skipped)
+        let equal = rest.iter().position(|c| !c.is_ascii_whitespace())?;
+        if rest[equal] != b'=' {
+            continue;
+        }
+        rest = &rest[equal + 1..];
+        rest = &rest[rest.iter().position(|c| !c.is_ascii_whitespace())?..];
+        let quote = rest[0];
+        if quote != b'"' && quote != b'\'' {
+            return None;
+        }
+        let value_len = rest[1..].iter().position(|c| *c == quote)?;
+        let value = &rest[1..1 + value_len];
+        rest = &rest[2 + value_len..];
+        if attribute == name {
+            return Some(value);
+        }
+    }
+}
+
+/// The elements where whitespace is significant, kept as is when reformatting.
+const PRESERVED_ELEMENTS: [&[u8]; 7] = [b"text", b"tspan", b"textPath", b"style", b"script", b"title", b"desc"];
+
+/// Returns the position of the first occurrence of `needle` in `haystack`.
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+/// Returns the position of the end of the tag at the start of `content` (the position of its `>`).
+/// 
+/// The `>` in attribute values are ignored.
+fn tag_end(content: &[u8]) -> Option<usize> {
+    let mut quote = None;
+    content.iter().position(|c| match quote {
+        Some(q) if *c == q => { quote = None; false }
+        Some(_) => false,
+        None if *c == b'"' || *c == b'\'' => { quote = Some(*c); false }
+        None => *c == b'>',
+    })
+}
+
+/// Minifies the given SVG content (see `Image::minify`).
+fn minify_svg(content: &[u8]) -> Vec<u8> {
+    let mut result = Vec::with_capacity(content.len());
+    let mut preserve = 0usize;
+    let mut rest = content;
+    while !rest.is_empty() {
+        // Copy the text before the next tag (if it is significant)
+        let start = rest.iter().position(|c| *c == b'<').unwrap_or(rest.len());
+        if preserve > 0 || !rest[..start].iter().all(u8::is_ascii_whitespace) {
+            result.extend_from_slice(&rest[..start]);
+        }
+        rest = &rest[start..];
+
+        // Find the end of the next construct (copying the rest if it is truncated)
+        let end = if rest.is_empty() {
+            break;
+        } else if rest.starts_with(b"<!--") {
+            find(rest, b"-->").map(|end| end + 3)
+        } else if rest.starts_with(b"<![CDATA[") {
+            find(rest, b"]]>").map(|end| end + 3)
+        } else if rest.starts_with(b"<?") {
+            find(rest, b"?>").map(|end| end + 2)
+        } else {
+            tag_end(rest).map(|end| end + 1)
+        };
+        let Some(end) = end else {
+            result.extend_from_slice(rest);
+            break;
+        };
+        let construct = &rest[..end];
+        rest = &rest[end..];
+
+        // Remove the comments and the XML declaration (if it is UTF-8)
+        if construct.starts_with(b"<!--") {
+            continue;
+        }
+        if construct.starts_with(b"<?xml") && matches!(construct.get(5), Some(c) if c.is_ascii_whitespace() || *c == b'?') {
+            let encoding = tag_attribute(&construct[5..construct.len() - 2], b"encoding");
+            if encoding.is_none_or(|encoding| encoding.eq_ignore_ascii_case(b"utf-8")) {
+                continue;
+            }
+        }
+        if construct.starts_with(b"<!") || construct.starts_with(b"<?") {
+            result.extend_from_slice(construct);
+            continue;
+        }
+
+        // Read the name of the tag
+        let closing = construct.get(1) == Some(&b'/');
+        let tag = &construct[if closing { 2 } else { 1 }..construct.len() - 1];
+        let name_len = tag.iter().position(|c| c.is_ascii_whitespace() || *c == b'/').unwrap_or(tag.len());
+        let name = &tag[..name_len];
+        let self_closing = tag.last() == Some(&b'/');
+
+        // Remove the metadata
+        if name == b"metadata" && !closing {
+            if !self_closing {
+                let close = find(rest, b"</metadata").and_then(|close| Some(close + tag_end(&rest[close..])? + 1));
+                rest = &rest[close.unwrap_or(rest.len())..];
+            }
+            continue;
+        }
+
+        // Track the elements where whitespace is significant
+        if PRESERVED_ELEMENTS.contains(&name) && !self_closing {
+            if closing {
+                preserve = preserve.saturating_sub(1);
+            } else {
+                preserve += 1;
+            }
+        }
+        result.extend_from_slice(construct);
+    }
+    result
+}
+
+/// Reindents the given SVG content (see `Image::pretty`).
+fn pretty_svg(content: &[u8]) -> Vec<u8> {
+
+    /// A kind of construct of the content.
+    #[derive(PartialEq)]
+    enum Kind {
+        Text,
+        Open,
+        Close,
+        Other,
+    }
+
+    // Split the content in constructs (copying the rest as text if it is truncated)
+    let mut constructs: Vec<(Kind, Range<usize>)> = Vec::new();
+    let mut rest = content;
+    while !rest.is_empty() {
+        let offset = content.len() - rest.len();
+        let start = rest.iter().position(|c| *c == b'<').unwrap_or(rest.len());
+        if !rest[..start].iter().all(u8::is_ascii_whitespace) {
+            constructs.push((Kind::Text, offset..offset + start));
+        }
+        rest = &rest[start..];
+        if rest.is_empty() {
+            break;
+        }
+
+        let (kind, end) = if rest.starts_with(b"<!--") {
+            (Kind::Other, find(rest, b"-->").map(|end| end + 3))
+        } else if rest.starts_with(b"<![CDATA[") {
+            (Kind::Text, find(rest, b"]]>").map(|end| end + 3))
+        } else if rest.starts_with(b"<?") {
+            (Kind::Other, find(rest, b"?>").map(|end| end + 2))
+        } else if rest.starts_with(b"<!") {
+            (Kind::Other, tag_end(rest).map(|end| end + 1))
+        } else if rest.starts_with(b"</") {
+            (Kind::Close, tag_end(rest).map(|end| end + 1))
+        } else {
+            let end = tag_end(rest).map(|end| end + 1);
+            let self_closing = matches!(end, Some(end) if rest[end - 2] == b'/');
+            (if self_closing { Kind::Other } else { Kind::Open }, end)
+        };
+        let Some(end) = end else {
+            constructs.push((Kind::Text, offset + start..content.len()));
+            break;
+        };
+        constructs.push((kind, offset + start..offset + start + end));
+        rest = &rest[end..];
+    }
+
+    // Find the elements written on a single line (ignoring the unbalanced tags)
+    let mut inline: Map<usize, usize> = Map::new();
+    let mut stack: Vec<(usize, bool)> = Vec::new();
+    for (i, (kind, range)) in constructs.iter().enumerate() {
+        match kind {
+            Kind::Text => if let Some((_, text)) = stack.last_mut() {
+                *text = true;
+            },
+            Kind::Open => {
+                let construct = &content[range.clone()];
+                let name_len = construct[1..].iter().position(|c| c.is_ascii_whitespace() || *c == b'>').unwrap_or(0);
+                stack.push((i, PRESERVED_ELEMENTS.contains(&&construct[1..1 + name_len])));
+            }
+            Kind::Close => if let Some((open, text)) = stack.pop() {
+                if text {
+                    inline.insert(open, i);
+                }
+            },
+            Kind::Other => (),
+        }
+    }
+
+    // Write each construct on its own line
+    let mut result = Vec::with_capacity(content.len() * 2);
+    let mut depth = 0usize;
+    let mut i = 0;
+    while i < constructs.len() {
+        let (kind, range) = &constructs[i];
+        if *kind == Kind::Close {
+            depth = depth.saturating_sub(1);
+        }
+        result.resize(result.len() + depth * 2, b' ');
+        match inline.get(&i) {
+            Some(&close) => {
+                result.extend_from_slice(&content[range.start..constructs[close].1.end]);
+                i = close;
+            }
+            None => {
+                result.extend_from_slice(&content[range.clone()]);
+                if *kind == Kind::Open {
+                    depth += 1;
+                }
+            }
+        }
+        result.push(b'\n');
+        i += 1;
+    }
+    result
+}
+
+/// Checks that the given content is a well-formed SVG document.
+#[cfg(feature = "std")]
+fn check_svg(content: &[u8]) -> Result<(), ImageError> {
+    let mut stack: Vec<&[u8]> = Vec::new();
+    let mut root: Option<&[u8]> = None;
+    let mut rest = content;
+    while !rest.is_empty() {
+        // Check the text before the next tag
+        let start = rest.iter().position(|c| *c == b'<').unwrap_or(rest.len());
+        if stack.is_empty() && !rest[..start].iter().all(u8::is_ascii_whitespace) {
+            return Err(ImageError::TextOutsideRoot);
+        }
+        rest = &rest[start..];
+        if rest.is_empty() {
+            break;
+        }
+
+        // Skip the declarations, comments and character data
+        let skip = [(&b"<?"[..], &b"?>"[..]), (b"<!--", b"-->"), (b"<![CDATA[", b"]]>"), (b"<!", b">")];
+        if let Some((_, end)) = skip.iter().find(|(start, _)| rest.starts_with(start)) {
+            rest = &rest[find(rest, end).ok_or(ImageError::Truncated)? + end.len()..];
+            continue;
+        }
+
+        // Find the end of the tag
+        let end = tag_end(rest).ok_or(ImageError::Truncated)?;
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+
+        // Read the name of the tag
+        let closing = tag.first() == Some(&b'/');
+        let tag = if closing { &tag[1..] } else { tag };
+        let name_len = tag.iter().position(|c| c.is_ascii_whitespace() || *c == b'/').unwrap_or(tag.len());
+        let name = &tag[..name_len];
+        let name_str = || String::from_utf8_lossy(name).into();
+
+        // Update the stack of open tags
+        if closing {
+            match stack.pop() {
+                Some(open) if open == name => (),
+                Some(open) => return Err(ImageError::MismatchedTag {
+                    expected: String::from_utf8_lossy(open).into(),
+                    found: name_str(),
+                }),
+                None => return Err(ImageError::UnexpectedClosingTag { name: name_str() }),
+            }
+        } else {
+            if stack.is_empty() {
+                if root.is_some() {
+                    return Err(ImageError::MultipleRoots);
+                }
+                root = Some(name);
+            }
+            if tag.last() != Some(&b'/') {
+                stack.push(name);
+            }
+        }
+    }
+
+    // Check the root element
+    if let Some(open) = stack.pop() {
+        return Err(ImageError::UnclosedTag { name: String::from_utf8_lossy(open).into() });
+    }
+    match root {
+        Some(b"svg") => Ok(()),
+        Some(name) => Err(ImageError::NotSvg { root: String::from_utf8_lossy(name).into() }),
+        None => Err(ImageError::NoRoot),
+    }
+}
+
+impl<T: Into<Box<[u8]>>> From<T> for Image {
+    /// Creates a new image from the given content.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `content` - The content of the image.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::Image;
+    /// 
+    /// let image = Image::from("Hello World!".as_bytes());
+    /// assert_eq!(image.content(), b"Hello World!");
+    /// ```
+    fn from(content: T) -> Self {
+        Image { content: content.into() }
+    }
+}
+
+impl AsRef<[u8]> for Image {
+    /// Returns the content of the image.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use std::io::Write;
+    /// use svggen::Image;
+    /// 
+    /// let image = Image::from("<svg/>".as_bytes());
+    /// 
+    /// let mut buffer: Vec<u8> = Vec::new();
+    /// buffer.write_all(image.as_ref()).unwrap();
+    /// 
+    /// assert_eq!(buffer, b"<svg/>");
+    /// ```
+    fn as_ref(&self) -> &[u8] {
+        &self.content
+    }
+}
+
+impl core::ops::Deref for Image {
+    type Target = [u8];
+
+    /// Returns the content of the image, so an image can be used as a slice.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::Image;
+    /// 
+    /// let image = Image::from("<svg/>".as_bytes());
+    /// 
+    /// assert_eq!(&image[..4], b"<svg");
+    /// assert_eq!(image.iter().filter(|c| **c == b'<').count(), 1);
+    /// assert!(image.starts_with(b"<svg"));
+    /// ```
+    fn deref(&self) -> &[u8] {
+        &self.content
+    }
+}
+
+impl PartialEq<[u8]> for Image {
+    /// Compares the content of the image with a slice of bytes.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::Image;
+    /// 
+    /// let image = Image::from("<svg/>".as_bytes());
+    /// 
+    /// assert!(image == *b"<svg/>".as_slice());
+    /// assert!(image != *b"<svg></svg>".as_slice());
+    /// ```
+    fn eq(&self, other: &[u8]) -> bool {
+        *self.content == *other
+    }
+}
+
+impl PartialEq<&[u8]> for Image {
+    /// Compares the content of the image with a slice of bytes.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::Image;
+    /// 
+    /// let image = Image::from("<svg/>".as_bytes());
+    /// 
+    /// assert_eq!(image, b"<svg/>" as &[u8]);
+    /// assert_ne!(image, b"<svg></svg>" as &[u8]);
+    /// 
+    /// // The comparison between images is still available
+    /// assert_eq!(image, Image::from("<svg/>".as_bytes()));
+    /// ```
+    fn eq(&self, other: &&[u8]) -> bool {
+        *self.content == **other
+    }
+}
+
+#[cfg(feature = "std")]
+impl Readable for Image {
+    /// There is no parsing error. The content is not parsed.
+    type ParseError = ();
+
+    /// Creates a new image from a reader.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `reader` - The reader to read the image from.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use rutil::read::Readable;
+    /// use svggen::Image;
+    /// 
+    /// let mut data = "Hello World!".as_bytes();
+    /// 
+    /// // data implements `io::Read` so we can use it as a reader
+    /// let image = Image::load(&mut data).unwrap();
+    /// assert_eq!(image.content(), b"Hello World!");
+    /// ```
+    fn load<R: std::io::Read>(reader: &mut R) -> Result<Self, ReadError<Self::ParseError>> {
+        let mut content = Vec::new();
+        reader.read_to_end(&mut content)?;
+        Ok(Image { content: content.into() })
+    }
+}
+
+/// The kind of content of an image, returned by `Image::kind`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImageKind {
+    /// An SVG image: markup whose root tag is `<svg>`.
+    Svg,
+
+    /// Some markup (XML or HTML) whose root tag is not `<svg>`.
+    Xml,
+
+    /// Anything else, such as a binary image.
+    Unknown,
+}
+
+// ========================= //
+// ====== IMAGE ERROR ====== //
+// ========================= //
+
+/// An error returned when an image is not a well-formed SVG, or is too large to be loaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImageError {
+    /// The image ends in the middle of a tag, a comment or a declaration.
+    Truncated,
+
+    /// The image has no root element.
+    NoRoot,
+
+    /// The image has more than one root element.
+    MultipleRoots,
+
+    /// The image has some text outside of its root element.
+    TextOutsideRoot,
+
+    /// The root element is not an `<svg>` element.
+    NotSvg {
+        /// The name of the root element.
+        root: Box<str>,
+    },
+
+    /// A tag is closed by a closing tag of another name.
+    MismatchedTag {
+        /// The name of the open tag.
+        expected: Box<str>,
+
+        /// The name of the closing tag.
+        found: Box<str>,
+    },
+
+    /// A closing tag has no matching open tag.
+    UnexpectedClosingTag {
+        /// The name of the closing tag.
+        name: Box<str>,
+    },
+
+    /// A tag is never closed.
+    UnclosedTag {
+        /// The name of the open tag.
+        name: Box<str>,
+    },
+
+    /// The image is larger than the maximum size (see `Image::load_limited`).
+    TooLarge {
+        /// The maximum size of the image, in bytes.
+        max_bytes: usize,
+    },
+}
+
+impl fmt::Display for ImageError {
+    /// Display an `ImageError`.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::ImageError;
+    /// 
+    /// let error = ImageError::MismatchedTag { expected: "g".into(), found: "svg".into() };
+    /// assert_eq!(error.to_string(), "Mismatched tag: expected </g>, found </svg>");
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ImageError::Truncated => write!(f, "Truncated image"),
+            ImageError::NoRoot => write!(f, "No root element"),
+            ImageError::MultipleRoots => write!(f, "Multiple root elements"),
+            ImageError::TextOutsideRoot => write!(f, "Text outside of the root element"),
+            ImageError::NotSvg { root } => write!(f, "Root element is not <svg>: <{}>", root),
+            ImageError::MismatchedTag { expected, found } => write!(f, "Mismatched tag: expected </{}>, found </{}>", expected, found),
+            ImageError::UnexpectedClosingTag { name } => write!(f, "Unexpected closing tag: </{}>", name),
+            ImageError::UnclosedTag { name } => write!(f, "Unclosed tag: <{}>", name),
+            ImageError::TooLarge { max_bytes } => write!(f, "Image too large: more than {} bytes", max_bytes),
+        }
+    }
+}
+
+impl error::Error for ImageError {}
+
+// ========================= //
+// ======= MODEL PART ====== //
+// ========================= //
+
+/// A model part used to create a model.
+/// 
+/// With the `serde` feature, a part is serialized as a map with a single
+/// entry whose key is the kind of part (`text`, `argument`, `named_argument`,
+/// `argument_or`, `argument_line`, `argument_slice`, `named_argument_or`,
+/// `conditional`, `repetition`, `item`, `set_var` or `get_var`).
+/// 
+/// # Examples
+/// 
+/// ```
+/// # #[cfg(feature = "serde")] {
+/// use svggen::ModelPart;
+/// 
+/// for (part, expected) in [
+///     (ModelPart::ArgumentLine(1), r#"{"argument_line":1}"#),
+///     (ModelPart::ArgumentSlice { index: 0, start: 2, end: 5 }, r#"{"argument_slice":{"index":0,"start":2,"end":5}}"#),
+/// ] {
+///     let json = serde_json::to_string(&part).unwrap();
+///     assert_eq!(json, expected);
+///     assert_eq!(serde_json::from_str::<ModelPart>(&json).unwrap(), part);
+/// 
+///     let bytes = bincode::serialize(&part).unwrap();
+///     assert_eq!(bincode::deserialize::<ModelPart>(&bytes).unwrap(), part);
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+#[non_exhaustive]
+pub enum ModelPart {
+    /// Some text.
+    Text(#[cfg_attr(feature = "serde", serde(with = "serde_bytes"))] Box<[u8]>),
+
+    /// An argument.
+    Argument(usize),
+
+    /// A named argument.
+    NamedArgument(Box<str>),
+
+    /// An argument, replaced by a default text if it is missing or empty.
+    ArgumentOr {
+        /// The index of the argument.
+        index: usize,
+
+        /// The text used if the argument is missing or empty.
+        default: Box<[u8]>,
+    },
+
+    /// An argument on its own line (`#GETLINE n`).
+    /// 
+    /// The argument is followed by a `\n`, unless it is empty: then nothing is
+    /// written, so the line is removed. A `ModelPart::Argument` on its own line
+    /// instead keeps the line terminators around it, even for an empty argument.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, ModelPart, Argument};
+    /// 
+    /// let block: Model = "<g>\n#GETLINE 0\n</g>".parse().unwrap();
+    /// let inline: Model = "<g>\n#GET 0\n</g>".parse().unwrap();
+    /// assert_eq!(block.parts()[1], ModelPart::ArgumentLine(0));
+    /// 
+    /// let shape = [Argument::from("<rect/>".as_bytes())];
+    /// assert_eq!(block.generate(&shape).unwrap().content(), b"<g>\n<rect/>\n</g>");
+    /// assert_eq!(inline.generate(&shape).unwrap().content(), b"<g>\n<rect/>\n</g>");
+    /// 
+    /// // An empty argument removes the whole line
+    /// assert_eq!(block.generate(&[Argument::Empty]).unwrap().content(), b"<g>\n</g>");
+    /// assert_eq!(inline.generate(&[Argument::Empty]).unwrap().content(), b"<g>\n\n</g>");
+    /// 
+    /// // Only an inline reference can be next to some text
+    /// let inline: Model = "<g>#GET 0</g>".parse().unwrap();
+    /// assert_eq!(inline.generate(&shape).unwrap().content(), b"<g><rect/></g>");
+    /// let text: Model = "<g>#GETLINE 0</g>".parse().unwrap();
+    /// assert_eq!(text.parts(), &[ModelPart::from("<g>#GETLINE 0</g>".as_bytes())]);
+    /// 
+    /// // The directive can be escaped
+    /// let escaped: Model = "##GETLINE 0\n".parse().unwrap();
+    /// assert_eq!(escaped.parts(), &[ModelPart::from("#GETLINE 0\n".as_bytes())]);
+    /// for model in [block, escaped] {
+    ///     assert_eq!(model.to_string().parse::<Model>().unwrap(), model);
+    /// }
+    /// ```
+    ArgumentLine(usize),
+
+    /// The bytes `start..end` of an argument (for example a fragment of a sprite sheet).
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, ModelPart, Image, Argument, GenerateError};
+    /// 
+    /// let model: Model = "<g>#GET 0[5..20]</g>".parse().unwrap();
+    /// assert_eq!(model.parts()[1], ModelPart::ArgumentSlice { index: 0, start: 5, end: 20 });
+    /// 
+    /// let sheet = Image::from("<svg><circle r=\"1\"/><rect/></svg>".as_bytes());
+    /// let image = model.generate(&[Argument::Image(&sheet)]).unwrap();
+    /// assert_eq!(image.content(), b"<g><circle r=\"1\"/></g>");
+    /// 
+    /// let small = Image::from("<svg/>".as_bytes());
+    /// let error = model.generate(&[Argument::Image(&small)]).unwrap_err();
+    /// assert!(matches!(error, GenerateError::SliceOutOfRange { index: 0, start: 5, end: 20, len: 6 }));
+    /// 
+    /// // Other arguments are sliced once generated
+    /// let bound = model.bind(&[Some(Argument::from("0123456789abcdefghijklmn".as_bytes()))]).unwrap();
+    /// assert_eq!(bound.to_string(), "<g>56789abcdefghij</g>");
+    /// assert_eq!(model.to_string(), "<g>#GET 0[5..20]</g>");
+    /// ```
+    ArgumentSlice {
+        /// The index of the argument.
+        index: usize,
+
+        /// The index of the first byte written.
+        start: usize,
+
+        /// The index after the last byte written.
+        end: usize,
+    },
+
+    /// A named argument, replaced by a default text if it is missing or empty.
+    NamedArgumentOr {
+        /// The name of the argument.
+        name: Box<str>,
+
+        /// The text used if the argument is missing or empty.
+        default: Box<[u8]>,
+    },
+
+    /// Some parts, only used if an argument is present and not empty.
+    Conditional {
+        /// The index of the argument.
+        index: usize,
+
+        /// The parts used if the argument is present and not empty.
+        body: Box<[ModelPart]>,
+    },
+
+    /// Some parts, used once for each item of a list argument.
+    Repetition {
+        /// The index of the list argument.
+        index: usize,
+
+        /// The parts used for each item.
+        body: Box<[ModelPart]>,
+    },
+
+    /// The current item of the innermost repetition block.
+    Item,
+
+    /// A variable set to a positional argument, which writes nothing.
+    /// 
+    /// A variable is set for the whole model: it can be used before the part
+    /// setting it and outside of the block containing it, but not by the nested
+    /// models. If several parts set the same variable, the last one is used.
+    SetVar {
+        /// The name of the variable.
+        name: Box<str>,
+
+        /// The index of the argument.
+        index: usize,
+    },
+
+    /// A variable, replaced by the argument it is set to.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, ModelPart, Argument, GenerateError};
+    /// 
+    /// let model = Model::from(vec![
+    ///     ModelPart::SetVar { name: "size".into(), index: 1 },
+    ///     ModelPart::GetVar("size".into()),
+    ///     ModelPart::from("x".as_bytes()),
+    ///     ModelPart::GetVar("size".into()),
+    /// ]);
+    /// 
+    /// let args = [Argument::Empty, Argument::number(16.0)];
+    /// assert_eq!(model.generate(&args).unwrap().content(), b"16x16");
+    /// 
+    /// let bound = model.bind(&[None, Some(Argument::number(32.0))]).unwrap();
+    /// assert_eq!(bound.to_string(), "32x32");
+    /// 
+    /// let error = model.generate(&[]).unwrap_err();
+    /// assert!(matches!(error, GenerateError::MissingArgument { index: 1 }));
+    /// 
+    /// let model = Model::from(vec![ModelPart::GetVar("size".into())]);
+    /// let error = model.generate(&args).unwrap_err();
+    /// assert!(matches!(error, GenerateError::MissingVariable { .. }));
+    /// ```
+    GetVar(Box<str>),
+}
+
+impl ModelPart {
+    /// Creates a positional argument part, checking its index.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `index` - The index of the argument.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{ModelPart, StructureError};
+    /// 
+    /// assert_eq!(ModelPart::argument(3), Ok(ModelPart::Argument(3)));
+    /// assert_eq!(ModelPart::argument(usize::MAX), Err(StructureError::IndexOverflow));
+    /// ```
+    /// 
+    /// # Errors
+    /// 
+    /// * `StructureError::IndexOverflow` - The index is `usize::MAX`, so the arity of a model using it would overflow.
+    pub fn argument(index: usize) -> Result<ModelPart, StructureError> {
+        if index == usize::MAX {
+            return Err(StructureError::IndexOverflow);
+        }
+        Ok(ModelPart::Argument(index))
+    }
+
+    /// Returns the number of bytes of a text part, or `None` for the other parts
+    /// (their size depends on the arguments).
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::ModelPart;
+    /// 
+    /// assert_eq!(ModelPart::from("<text>".as_bytes()).byte_len(), Some(6));
+    /// assert_eq!(ModelPart::Argument(0).byte_len(), None);
+    /// assert_eq!(ModelPart::Item.byte_len(), None);
+    /// ```
+    pub fn byte_len(&self) -> Option<usize> {
+        match self {
+            ModelPart::Text(content) => Some(content.len()),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ModelPart {
+    /// Display a `ModelPart`.
+    /// 
+    /// A text is displayed as is (lossy UTF-8), and the other parts as in
+    /// the source of a model.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::ModelPart;
+    /// 
+    /// assert_eq!(ModelPart::from("<text>".as_bytes()).to_string(), "<text>");
+    /// assert_eq!(ModelPart::Argument(3).to_string(), "#GET 3");
+    /// assert_eq!(ModelPart::NamedArgument("title".into()).to_string(), "#GET title");
+    /// assert_eq!(ModelPart::ArgumentOr { index: 0, default: b"none".to_vec().into() }.to_string(), "#GET 0|none|");
+    /// assert_eq!(ModelPart::ArgumentSlice { index: 0, start: 2, end: 8 }.to_string(), "#GET 0[2..8]");
+    /// assert_eq!(ModelPart::ArgumentLine(1).to_string(), "#GETLINE 1\n");
+    /// assert_eq!(ModelPart::Item.to_string(), "#ITEM");
+    /// assert_eq!(ModelPart::SetVar { name: "accent".into(), index: 0 }.to_string(), "#SET accent = 0\n");
+    /// assert_eq!(ModelPart::GetVar("accent".into()).to_string(), "#VAR accent");
+    /// 
+    /// let part = ModelPart::Conditional { index: 1, body: vec![ModelPart::Argument(1)].into() };
+    /// assert_eq!(part.to_string(), "#IF 1\n#GET 1\n#END\n");
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ModelPart::Text(content) => write!(f, "{}", String::from_utf8_lossy(content)),
+            part => fmt_parts(core::slice::from_ref(part), f, &ModelParser::new(), &mut true),
+        }
+    }
+}
+
+impl<T: Into<Box<[u8]>>> From<T> for ModelPart {
+    /// Creates a new text model part from the given content.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `content` - The content of the text model part.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::ModelPart;
+    /// 
+    /// let part = ModelPart::from("Hello World!".as_bytes());
+    /// assert_eq!(part, ModelPart::Text(b"Hello World!".to_vec().into()));
+    /// ```
+    fn from(content: T) -> Self {
+        ModelPart::Text(content.into())
+    }
+}
+
+// ========================= //
+// ===== MODEL ARGUMENT ==== //
+// ========================= //
+
+/// A model argument used to pass arguments to a model to generate an image.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Argument<'a> {
+    /// Some text, written as is.
+    /// 
+    /// The content is meant to be text (usually UTF-8), use `Argument::Bytes` for
+    /// binary data. This is not safe for untrusted data, use `Argument::EscapedText`
+    /// (or `Argument::into_escaped`) instead.
+    Text(Box<[u8]>),
+
+    /// Some raw bytes, written as is.
+    /// 
+    /// Unlike `Argument::Text`, the content is not text: it is never escaped by
+    /// `Argument::into_escaped`, and it is summarized when displayed.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, Argument};
+    /// 
+    /// let model: Model = "<g>#GET 0</g>".parse().unwrap();
+    /// let bytes = Argument::Bytes(b"<\xff>".to_vec().into());
+    /// 
+    /// assert_eq!(model.generate(&[bytes.clone()]).unwrap().content(), b"<g><\xff></g>");
+    /// assert_eq!(bytes.to_string(), "<bytes: 3 bytes>");
+    /// assert_eq!(bytes.len(), Some(3));
+    /// assert!(!bytes.is_text());
+    /// ```
+    Bytes(Box<[u8]>),
+
+    /// Some text, escaped for XML when written.
+    /// 
+    /// The characters `&`, `<`, `>`, `"` and `'` are replaced by their XML entities,
+    /// so the text is safe to use for untrusted data (in elements or quoted attributes).
+    EscapedText(Box<[u8]>),
+
+    /// An image.
+    Image(&'a Image),
+
+    /// An owned image, written like `Argument::Image`.
+    /// 
+    /// This avoids keeping an image alive outside of the arguments, for example
+    /// when the image is generated only to be given to another model.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, Argument};
+    /// 
+    /// let icon: Model = "<circle r=\"#GET 0\"/>".parse().unwrap();
+    /// let model: Model = "<svg>#GET 0#GET 1</svg>".parse().unwrap();
+    /// 
+    /// let args = [
+    ///     Argument::OwnedImage(icon.generate(&[Argument::number(1.0)]).unwrap()),
+    ///     Argument::OwnedImage(icon.generate(&[Argument::number(2.0)]).unwrap()),
+    /// ];
+    /// 
+    /// let image = model.generate(&args).unwrap();
+    /// assert_eq!(image.content(), b"<svg><circle r=\"1\"/><circle r=\"2\"/></svg>");
+    /// assert_eq!(model.output_size(&args), Some(image.content().len()));
+    /// ```
+    OwnedImage(Image),
+
+    /// A nested model, generated with its own arguments.
+    /// 
+    /// Since the arguments are owned, a nested model cannot refer to itself and
+    /// the expansion always terminates. The depth of nested models is still
+    /// limited to `Model::DEFAULT_MAX_DEPTH` (see `Model::generate_with_limit`).
+    Model {
+        /// The nested model.
+        model: &'a Model,
+
+        /// The arguments of the nested model.
+        args: Vec<Argument<'a>>,
+    },
+
+    /// A lazy argument, computed only when the model uses it.
+    Lazy(&'a dyn LazyContent),
+
+    /// A number, formatted without exponent or locale-dependent separator.
+    Number(NumberFmt),
+
+    /// A list of arguments, iterated by a `#FOR` block.
+    /// 
+    /// When it is used as a plain argument, its items are written one after the other.
+    List(Vec<Argument<'a>>),
+
+    /// An empty argument.
+    Empty,
+}
+
+impl Argument<'_> {
+    /// Creates a new escaped text argument from the given content.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `content` - The content of the text argument.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, Argument};
+    /// 
+    /// let model: Model = "<text title=\"#GET 0\">#GET 0</text>".parse().unwrap();
+    /// let args = [Argument::escaped("Tom & Jerry <3 \"'>".as_bytes())];
+    /// 
+    /// let image = model.generate(&args).unwrap();
+    /// assert_eq!(
+    ///     image.content(),
+    ///     b"<text title=\"Tom &amp; Jerry &lt;3 &quot;&apos;&gt;\">Tom &amp; Jerry &lt;3 &quot;&apos;&gt;</text>",
+    /// );
+    /// assert_eq!(model.output_size(&args), Some(image.content().len()));
+    /// ```
+    pub fn escaped<T: Into<Box<[u8]>>>(content: T) -> Self {
+        Argument::EscapedText(content.into())
+    }
+
+    /// Creates a new number argument, written with as many decimals as needed.
+    /// 
+    /// Use `Argument::Number` with `NumberFmt::decimals` for a fixed number of decimals.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `value` - The value of the number.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, Argument, NumberFmt};
+    /// 
+    /// let model: Model = "<rect width=\"#GET 0\" height=\"#GET 1\"/>".parse().unwrap();
+    /// let args = [Argument::number(1000000.0), Argument::Number(NumberFmt::new(2.0 / 3.0).decimals(2))];
+    /// 
+    /// let image = model.generate(&args).unwrap();
+    /// assert_eq!(image.content(), b"<rect width=\"1000000\" height=\"0.67\"/>");
+    /// assert_eq!(model.output_size(&args), Some(image.content().len()));
+    /// ```
+    pub fn number(value: f64) -> Self {
+        Argument::Number(NumberFmt::new(value))
+    }
+
+    /// Returns the number of bytes written for the argument, if it is known without generating anything.
+    /// 
+    /// Returns `None` for a nested model and a lazy argument (which is not computed),
+    /// and for a list containing one of them.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, Image, Argument, LazyArgument};
+    /// 
+    /// let image = Image::from("<svg/>".as_bytes());
+    /// let model: Model = "<g/>".parse().unwrap();
+    /// let lazy = LazyArgument::new(|| "<rect/>".as_bytes());
+    /// 
+    /// assert_eq!(Argument::from("Hello".as_bytes()).len(), Some(5));
+    /// assert_eq!(Argument::escaped("A & B".as_bytes()).len(), Some(9));
+    /// assert_eq!(Argument::Image(&image).len(), Some(6));
+    /// assert_eq!(Argument::OwnedImage(image.clone()).len(), Some(6));
+    /// assert_eq!(Argument::Model { model: &model, args: vec![] }.len(), None);
+    /// assert_eq!(Argument::Lazy(&lazy).len(), None);
+    /// assert_eq!(Argument::number(1.5).len(), Some(3));
+    /// assert_eq!(Argument::List(vec![Argument::number(10.0), Argument::Image(&image)]).len(), Some(8));
+    /// assert_eq!(Argument::List(vec![Argument::Lazy(&lazy)]).len(), None);
+    /// assert_eq!(Argument::Empty.len(), Some(0));
+    /// ```
+    pub fn len(&self) -> Option<usize> {
+        Some(match self {
+            Argument::Text(content) | Argument::Bytes(content) => content.len(),
+            Argument::EscapedText(content) => escaped_len(content),
+            Argument::Image(image) => image.len(),
+            Argument::OwnedImage(image) => image.len(),
+            Argument::Model { .. } | Argument::Lazy(_) => return None,
+            Argument::Number(number) => number.to_string().len(),
+            Argument::List(items) => items.iter().map(Argument::len).sum::<Option<usize>>()?,
+            Argument::Empty => 0,
+        })
+    }
+
+    /// Returns `true` if the argument writes nothing, so its length is `Some(0)` (see `Argument::len`).
+    /// 
+    /// An argument whose length is unknown (a nested model or a lazy content)
+    /// is not empty.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::Argument;
+    /// 
+    /// assert!(Argument::Empty.is_empty());
+    /// assert!(Argument::from("".as_bytes()).is_empty());
+    /// assert!(Argument::List(vec![]).is_empty());
+    /// assert!(!Argument::from("Hello".as_bytes()).is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.len() == Some(0)
+    }
+
+    /// Returns `true` if the argument is `Argument::Empty`.
+    /// 
+    /// This is the meaning of empty used by the `#IF` blocks and the default values:
+    /// a text without any byte is not `Argument::Empty`, even if it writes nothing.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::Argument;
+    /// 
+    /// assert!(Argument::Empty.is_empty_variant());
+    /// assert!(!Argument::from("".as_bytes()).is_empty_variant());
+    /// assert!(!Argument::List(vec![]).is_empty_variant());
+    /// ```
+    pub fn is_empty_variant(&self) -> bool {
+        matches!(self, Argument::Empty)
+    }
+
+    /// Returns `true` if the argument is an image, borrowed or owned.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Image, Argument};
+    /// 
+    /// let image = Image::from("<svg/>".as_bytes());
+    /// 
+    /// assert!(Argument::Image(&image).is_image());
+    /// assert!(Argument::OwnedImage(image.clone()).is_image());
+    /// assert!(!Argument::from("<svg/>".as_bytes()).is_image());
+    /// 
+    /// // A non-empty image
+    /// let arg = Argument::Image(&image);
+    /// assert!(arg.is_image() && arg.len() > Some(0));
+    /// ```
+    pub fn is_image(&self) -> bool {
+        matches!(self, Argument::Image(_) | Argument::OwnedImage(_))
+    }
+
+    /// Returns `true` if the argument is a text, escaped or not.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::Argument;
+    /// 
+    /// assert!(Argument::from("Hello".as_bytes()).is_text());
+    /// assert!(Argument::escaped("A & B".as_bytes()).is_text());
+    /// assert!(!Argument::number(1.0).is_text());
+    /// assert!(!Argument::Empty.is_text());
+    /// ```
+    pub fn is_text(&self) -> bool {
+        matches!(self, Argument::Text(_) | Argument::EscapedText(_))
+    }
+
+    /// Returns the argument with its texts escaped for XML, including in lists.
+    /// 
+    /// An `Argument::Text` becomes an `Argument::EscapedText`. The other arguments
+    /// (including `Argument::Bytes`, which is not text) are unchanged.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, Argument};
+    /// 
+    /// let model: Model = "<text>#GET 0</text>".parse().unwrap();
+    /// 
+    /// let text = Argument::from("A & B").into_escaped();
+    /// assert_eq!(text, Argument::escaped("A & B".as_bytes()));
+    /// assert_eq!(model.generate(&[text]).unwrap().content(), b"<text>A &amp; B</text>");
+    /// 
+    /// let bytes = Argument::Bytes(b"A & B".to_vec().into()).into_escaped();
+    /// assert_eq!(model.generate(&[bytes]).unwrap().content(), b"<text>A & B</text>");
+    /// ```
+    pub fn into_escaped(self) -> Self {
+        match self {
+            Argument::Text(content) => Argument::EscapedText(content),
+            Argument::List(items) => Argument::List(items.into_iter().map(Argument::into_escaped).collect()),
+            arg => arg,
+        }
+    }
+}
+
+/// Returns the XML entity replacing the given byte, if it must be escaped.
+fn xml_entity(byte: u8) -> Option<&'static [u8]> {
+    match byte {
+        b'&' => Some(b"&amp;"),
+        b'<' => Some(b"&lt;"),
+        b'>' => Some(b"&gt;"),
+        b'"' => Some(b"&quot;"),
+        b'\'' => Some(b"&apos;"),
+        _ => None,
+    }
+}
+
+/// Returns the length of the given content once escaped for XML.
+fn escaped_len(content: &[u8]) -> usize {
+    content.iter().map(|byte| xml_entity(*byte).map_or(1, <[u8]>::len)).sum()
+}
+
+/// Writes the given content escaped for XML.
+fn write_escaped<W: Output + ?Sized>(writer: &mut W, content: &[u8]) -> Result<(), GenerateError> {
+    let mut start = 0;
+    for (i, byte) in content.iter().enumerate() {
+        if let Some(entity) = xml_entity(*byte) {
+            writer.write_bytes(&content[start..i])?;
+            writer.write_bytes(entity)?;
+            start = i + 1;
+        }
+    }
+    writer.write_bytes(&content[start..])
+}
+
+impl fmt::Display for Argument<'_> {
+    /// Display an `Argument`, for logging and debugging.
+    /// 
+    /// Texts are displayed as is (lossy UTF-8, not escaped) and numbers as they are
+    /// written. The other arguments are summarized, and lazy arguments are not computed.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, Image, Argument, LazyArgument};
+    /// 
+    /// let image = Image::from("<svg/>".as_bytes());
+    /// let model: Model = "<g>#GET 0</g>".parse().unwrap();
+    /// let lazy = LazyArgument::new(|| "<rect/>".as_bytes());
+    /// 
+    /// assert_eq!(Argument::from("Hello".as_bytes()).to_string(), "Hello");
+    /// assert_eq!(Argument::escaped("A & B".as_bytes()).to_string(), "A & B");
+    /// assert_eq!(Argument::Image(&image).to_string(), "<image: 6 bytes>");
+    /// assert_eq!(Argument::OwnedImage(image.clone()).to_string(), "<image: 6 bytes>");
+    /// assert_eq!(Argument::Model { model: &model, args: vec![] }.to_string(), "<model: 3 parts, 0 arguments>");
+    /// assert_eq!(Argument::Lazy(&lazy).to_string(), "<lazy>");
+    /// assert_eq!(Argument::number(1.5).to_string(), "1.5");
+    /// assert_eq!(Argument::List(vec![Argument::number(1.0), Argument::Empty]).to_string(), "[1, <empty>]");
+    /// assert_eq!(Argument::Empty.to_string(), "<empty>");
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Argument::Text(content) | Argument::EscapedText(content) => write!(f, "{}", String::from_utf8_lossy(content)),
+            Argument::Bytes(content) => write!(f, "<bytes: {} bytes>", content.len()),
+            Argument::Image(image) => write!(f, "<image: {} bytes>", image.content().len()),
+            Argument::OwnedImage(image) => write!(f, "<image: {} bytes>", image.content().len()),
+            Argument::Model { model, args } => write!(f, "<model: {} parts, {} arguments>", model.parts().len(), args.len()),
+            Argument::Lazy(_) => write!(f, "<lazy>"),
+            Argument::Number(number) => write!(f, "{}", number),
+            Argument::List(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Argument::Empty => write!(f, "<empty>"),
+        }
+    }
+}
+
+impl From<Box<[u8]>> for Argument<'static> {
+    /// Creates a new text argument from the given content.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `content` - The content of the text argument.
+    fn from(content: Box<[u8]>) -> Self {
+        Argument::Text(content)
+    }
+}
+
+impl From<Vec<u8>> for Argument<'static> {
+    /// Creates a new text argument from the given content.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `content` - The content of the text argument.
+    fn from(content: Vec<u8>) -> Self {
+        Argument::Text(content.into())
+    }
+}
+
+impl From<&[u8]> for Argument<'static> {
+    /// Creates a new text argument from a copy of the given content.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `content` - The content of the text argument.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::Argument;
+    /// 
+    /// let arg = Argument::from("Hello World!".as_bytes());
+    /// assert_eq!(arg, Argument::Text(b"Hello World!".to_vec().into()));
+    /// ```
+    fn from(content: &[u8]) -> Self {
+        Argument::Text(content.into())
+    }
+}
+
+impl<const N: usize> From<[u8; N]> for Argument<'static> {
+    /// Creates a new text argument from the given content.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `content` - The content of the text argument.
+    fn from(content: [u8; N]) -> Self {
+        Argument::Text(content.into())
+    }
+}
+
+impl From<&str> for Argument<'static> {
+    /// Creates a new text argument from a copy of the given string.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `content` - The content of the text argument.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::Argument;
+    /// 
+    /// assert_eq!(Argument::from("Hello"), Argument::from("Hello".as_bytes()));
+    /// ```
+    fn from(content: &str) -> Self {
+        Argument::Text(content.as_bytes().into())
+    }
+}
+
+impl From<String> for Argument<'static> {
+    /// Creates a new text argument from the given string, without copying it.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `content` - The content of the text argument.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::Argument;
+    /// 
+    /// assert_eq!(Argument::from(format!("{}px", 12)), Argument::from("12px"));
+    /// ```
+    fn from(content: String) -> Self {
+        Argument::Text(content.into_bytes().into())
+    }
+}
+
+impl From<Box<str>> for Argument<'static> {
+    /// Creates a new text argument from the given string, without copying it.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `content` - The content of the text argument.
+    fn from(content: Box<str>) -> Self {
+        Argument::Text(content.into_boxed_bytes())
+    }
+}
+
+impl From<Cow<'_, [u8]>> for Argument<'static> {
+    /// Creates a new text argument from the given content, copying it only if it is borrowed.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `content` - The content of the text argument.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use std::borrow::Cow;
+    /// use svggen::Argument;
+    /// 
+    /// assert_eq!(Argument::from(Cow::Borrowed("Hi".as_bytes())), Argument::from("Hi"));
+    /// assert_eq!(Argument::from(Cow::<[u8]>::Owned(b"Hi".to_vec())), Argument::from("Hi"));
+    /// assert_eq!(Argument::from(Box::<str>::from("Hi")), Argument::from("Hi"));
+    /// ```
+    fn from(content: Cow<'_, [u8]>) -> Self {
+        Argument::Text(content.into_owned().into())
+    }
+}
+
+impl From<char> for Argument<'static> {
+    /// Creates a new text argument from the given character, encoded in UTF-8.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `content` - The content of the text argument.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::Argument;
+    /// 
+    /// assert_eq!(Argument::from('é'), Argument::from("é"));
+    /// ```
+    fn from(content: char) -> Self {
+        Argument::from(content.to_string())
+    }
+}
+
+impl From<i32> for Argument<'static> {
+    /// Creates a new text argument with the given integer, written in decimal.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `value` - The value of the argument.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::Argument;
+    /// 
+    /// assert_eq!(Argument::from(42), Argument::from("42"));
+    /// assert_eq!(Argument::from(-7), Argument::from("-7"));
+    /// ```
+    fn from(value: i32) -> Self {
+        Argument::from(value.to_string())
+    }
+}
+
+impl From<i64> for Argument<'static> {
+    /// Creates a new text argument with the given integer, written in decimal.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `value` - The value of the argument.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::Argument;
+    /// 
+    /// assert_eq!(Argument::from(i64::MIN), Argument::from("-9223372036854775808"));
+    /// ```
+    fn from(value: i64) -> Self {
+        Argument::from(value.to_string())
+    }
+}
+
+impl From<u32> for Argument<'static> {
+    /// Creates a new text argument with the given integer, written in decimal.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `value` - The value of the argument.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::Argument;
+    /// 
+    /// assert_eq!(Argument::from(42u32), Argument::from("42"));
+    /// ```
+    fn from(value: u32) -> Self {
+        Argument::from(value.to_string())
+    }
+}
+
+impl From<u64> for Argument<'static> {
+    /// Creates a new text argument with the given integer, written in decimal.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `value` - The value of the argument.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::Argument;
+    /// 
+    /// assert_eq!(Argument::from(u64::MAX), Argument::from("18446744073709551615"));
+    /// ```
+    fn from(value: u64) -> Self {
+        Argument::from(value.to_string())
+    }
+}
+
+impl From<usize> for Argument<'static> {
+    /// Creates a new text argument with the given integer, written in decimal.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `value` - The value of the argument.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::Argument;
+    /// 
+    /// let items = ["a", "b", "c"];
+    /// assert_eq!(Argument::from(items.len()), Argument::from("3"));
+    /// ```
+    fn from(value: usize) -> Self {
+        Argument::from(value.to_string())
+    }
+}
+
+impl From<f64> for Argument<'static> {
+    /// Creates a new text argument with the given number, written in decimal.
+    /// 
+    /// The number is written as `Argument::number` writes it, never with an
+    /// exponent, and the argument is a text like the integer ones. Use
+    /// `Argument::number` to keep the number and choose its formatting.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `value` - The value of the argument.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, Argument};
+    /// 
+    /// assert_eq!(Argument::from(1.5), Argument::from("1.5"));
+    /// assert_eq!(Argument::from(2.0), Argument::from(2));
+    /// 
+    /// let model: Model = "<circle r=\"#GET 0\"/>".parse().unwrap();
+    /// let image = model.generate(&[Argument::from(2.5)]).unwrap();
+    /// assert_eq!(image.content(), b"<circle r=\"2.5\"/>");
+    /// let image = model.generate(&[Argument::from(-0.0)]).unwrap();
+    /// assert_eq!(image.content(), b"<circle r=\"0\"/>");
+    /// let image = model.generate(&[Argument::from(1e21)]).unwrap();
+    /// assert_eq!(image.content(), b"<circle r=\"1000000000000000000000\"/>");
+    /// ```
+    fn from(value: f64) -> Self {
+        Argument::from(NumberFmt::new(value).to_string())
+    }
+}
+
+/// The positional and named arguments given to a model.
+/// 
+/// A `#GET n` reference always uses the positional argument of index `n`, and
+/// a `#GET name` reference always uses the named argument `name`. There is no
+/// fallback from one kind to the other, and since a name cannot start with a
+/// digit, the two kinds never overlap.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use svggen::{Model, Argument, Arguments};
+/// 
+/// let model: Model = "<text fill=\"#GET color\">#GET 0</text>".parse().unwrap();
+/// 
+/// let args = Arguments::new()
+///     .with(Argument::from("Hello".as_bytes()))
+///     .with_named("color", Argument::from("red".as_bytes()));
+/// 
+/// assert_eq!(args.get_positional(0), Some(&Argument::from("Hello".as_bytes())));
+/// assert_eq!(args.get_named("color"), Some(&Argument::from("red".as_bytes())));
+/// 
+/// let image = model.generate_with(&args).unwrap();
+/// assert_eq!(image.content(), b"<text fill=\"red\">Hello</text>");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Arguments<'a> {
+    /// The positional arguments.
+    positional: Vec<Argument<'a>>,
+
+    /// The named arguments.
+    named: Map<&'a str, Argument<'a>>,
+}
+
+impl<'a> Arguments<'a> {
+    /// Creates a new empty set of arguments.
+    pub fn new() -> Self {
+        Arguments::default()
+    }
+
+    /// Adds a positional argument, after the previous ones.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `arg` - The argument to add.
+    pub fn with(mut self, arg: Argument<'a>) -> Self {
+        self.positional.push(arg);
+        self
+    }
+
+    /// Adds a named argument, replacing the argument with the same name if there is one.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `name` - The name of the argument.
+    /// * `arg` - The argument to add.
+    pub fn with_named(mut self, name: &'a str, arg: Argument<'a>) -> Self {
+        self.named.insert(name, arg);
+        self
+    }
+
+    /// Returns the positional argument of the given index, if it is given.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `index` - The index of the argument.
+    pub fn get_positional(&self, index: usize) -> Option<&Argument<'a>> {
+        self.positional.get(index)
+    }
+
+    /// Returns the named argument of the given name, if it is given.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `name` - The name of the argument.
+    pub fn get_named(&self, name: &str) -> Option<&Argument<'a>> {
+        self.named.get(name)
+    }
+}
+
+impl<'a> From<Vec<Argument<'a>>> for Arguments<'a> {
+    /// Creates arguments with the given positional arguments.
+    fn from(positional: Vec<Argument<'a>>) -> Self {
+        Arguments { positional, named: Map::new() }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a> From<HashMap<&'a str, Argument<'a>>> for Arguments<'a> {
+    /// Creates arguments with the given named arguments.
+    fn from(named: HashMap<&'a str, Argument<'a>>) -> Self {
+        Arguments { positional: Vec::new(), named }
+    }
+}
+
+/// A number with its formatting, used by `Argument::Number`.
+/// 
+/// The number is never written with an exponent and always uses `.` as the
+/// decimal separator: `1000000.0` is written `1000000` and `0.0000001` is written
+/// `0.0000001`. By default the shortest decimals that give back the same `f64`
+/// are used, and `NumberFmt::decimals` rounds the number to a fixed number of
+/// decimals instead (rounding half to even). A negative zero is written `0`.
+/// Integers are exact up to 2^53.
+#[derive(Debug, Clone, Copy)]
+pub struct NumberFmt {
+    /// The value of the number.
+    value: f64,
+
+    /// The fixed number of decimals, if any.
+    decimals: Option<usize>,
+}
+
+impl NumberFmt {
+    /// Creates a new number, written with as many decimals as needed.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `value` - The value of the number.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::NumberFmt;
+    /// 
+    /// assert_eq!(NumberFmt::new(1000000.0).to_string(), "1000000");
+    /// assert_eq!(NumberFmt::new(1e-7).to_string(), "0.0000001");
+    /// assert_eq!(NumberFmt::new(-12.5).to_string(), "-12.5");
+    /// assert_eq!(NumberFmt::new(-0.0).to_string(), "0");
+    /// ```
+    pub fn new(value: f64) -> Self {
+        NumberFmt { value, decimals: None }
+    }
+
+    /// Sets a fixed number of decimals.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `decimals` - The number of decimals to write.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::NumberFmt;
+    /// 
+    /// assert_eq!(NumberFmt::new(3.14159).decimals(2).to_string(), "3.14");
+    /// assert_eq!(NumberFmt::new(12.0).decimals(1).to_string(), "12.0");
+    /// assert_eq!(NumberFmt::new(2.5).decimals(0).to_string(), "2");
+    /// assert_eq!(NumberFmt::new(-0.001).decimals(2).to_string(), "0.00");
+    /// ```
+    pub fn decimals(mut self, decimals: usize) -> Self {
+        self.decimals = Some(decimals);
+        self
+    }
+
+    /// Returns the value of the number.
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+}
+
+impl fmt::Display for NumberFmt {
+    /// Display a `NumberFmt`, as it is written in a generated image.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let text = match self.decimals {
+            Some(decimals) => format!("{:.*}", decimals, self.value),
+            None => format!("{}", self.value),
+        };
+
+        // Do not write the sign of a number rounded to zero
+        match text.strip_prefix('-') {
+            Some(rest) if rest.bytes().all(|c| c == b'0' || c == b'.') => write!(f, "{}", rest),
+            _ => write!(f, "{}", text),
+        }
+    }
+}
+
+impl PartialEq for NumberFmt {
+    /// Two numbers are equal if they have the same bits and the same formatting.
+    fn eq(&self, other: &Self) -> bool {
+        self.value.to_bits() == other.value.to_bits() && self.decimals == other.decimals
+    }
+}
+
+impl Eq for NumberFmt {}
+
+impl Hash for NumberFmt {
+    /// Hashes the bits and the formatting of the number, as compared by `PartialEq`.
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.value.to_bits().hash(state);
+        self.decimals.hash(state);
+    }
+}
+
+// ========================= //
+// ===== LAZY ARGUMENT ===== //
+// ========================= //
+
+/// A content that is only computed when it is needed.
+pub trait LazyContent {
+    /// Returns the content, computing it if needed.
+    fn content(&self) -> &[u8];
+}
+
+impl fmt::Debug for dyn LazyContent + '_ {
+    /// Display a `LazyContent` with its address (the content is not computed).
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "LazyContent({:p})", self)
+    }
+}
+
+impl PartialEq for dyn LazyContent + '_ {
+    /// Two lazy contents are equal if they are the same object.
+    /// 
+    /// The contents are not computed.
+    fn eq(&self, other: &Self) -> bool {
+        core::ptr::addr_eq(self, other)
+    }
+}
+
+impl Eq for dyn LazyContent + '_ {}
+
+impl Hash for dyn LazyContent + '_ {
+    /// Hashes the address of a `LazyContent`, as compared by `PartialEq`.
+    /// 
+    /// The content is not computed.
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        core::ptr::hash(self as *const Self as *const (), state)
+    }
+}
+
+/// A lazy argument that computes its content the first time it is used.
+/// 
+/// The content is memoized: the function is called at most once, even if the
+/// model references the argument multiple times or is generated multiple times.
+/// If the model never references the argument, the function is never called.
+pub struct LazyArgument<F> {
+    /// The function computing the content (`None` once called).
+    init: Cell<Option<F>>,
+
+    /// The computed content.
+    value: OnceCell<Box<[u8]>>,
+}
+
+impl<F> LazyArgument<F> {
+    /// Creates a new lazy argument from the function computing its content.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `init` - The function computing the content.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use std::borrow::Cow;
+    /// use std::cell::Cell;
+    /// use svggen::{Model, Argument, LazyArgument};
+    /// 
+    /// let model: Model = "<g>#GET 0 #GET 0</g>".parse().unwrap();
+    /// 
+    /// let calls = Cell::new(0);
+    /// let chart = LazyArgument::new(|| {
+    ///     calls.set(calls.get() + 1);
+    ///     Cow::Borrowed("<path/>".as_bytes())
+    /// });
+    /// 
+    /// let image = model.generate(&[Argument::Lazy(&chart)]).unwrap();
+    /// assert_eq!(image.content(), b"<g><path/> <path/></g>");
+    /// 
+    /// // The content is computed only once
+    /// assert_eq!(calls.get(), 1);
+    /// ```
+    /// 
+    /// The function is not called if the argument is not used:
+    /// 
+    /// ```
+    /// use svggen::{Model, Argument, LazyArgument};
+    /// 
+    /// let model: Model = "<g>#GET 0</g>".parse().unwrap();
+    /// let chart = LazyArgument::new(|| -> Vec<u8> { panic!("not used") });
+    /// 
+    /// let args = [Argument::from("<rect/>".as_bytes()), Argument::Lazy(&chart)];
+    /// let image = model.generate(&args).unwrap();
+    /// 
+    /// assert_eq!(image.content(), b"<g><rect/></g>");
+    /// assert!(!chart.is_computed());
+    /// ```
+    pub fn new(init: F) -> Self {
+        LazyArgument { init: Cell::new(Some(init)), value: OnceCell::new() }
+    }
+
+    /// Returns `true` if the content has already been computed.
+    pub fn is_computed(&self) -> bool {
+        self.value.get().is_some()
+    }
+}
+
+impl<T: Into<Box<[u8]>>, F: FnOnce() -> T> LazyContent for LazyArgument<F> {
+    /// Returns the content, calling the function the first time.
+    /// 
+    /// # Panics
+    /// 
+    /// Panics if the function tries to get the content of its own argument.
+    fn content(&self) -> &[u8] {
+        self.value.get_or_init(|| match self.init.take() {
+            Some(init) => init().into(),
+            None => panic!("LazyArgument initialized recursively"),
+        })
+    }
+}
+
+impl<F> fmt::Debug for LazyArgument<F> {
+    /// Display a `LazyArgument` with its content if it has been computed.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LazyArgument").field("value", &self.value.get()).finish()
+    }
+}
+
+// ========================= //
+// ==== ARGUMENT SOURCE ==== //
+// ========================= //
+
+/// A source giving the positional arguments by index (see `Model::generate` and `Model::write`).
+/// 
+/// The arguments are resolved only when the generation reaches a part using
+/// them, and each index is resolved at most once per generation. Slices, arrays
+/// and vectors of arguments are sources, used in place (see `ArgumentSource::as_slice`),
+/// as well as the functions taking an index and returning an optional owned
+/// argument (`Argument<'static>`). A source of borrowed arguments implements the
+/// trait to tie them to itself.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use svggen::{Model, Argument, ArgumentSource};
+/// 
+/// /// The rows of a table, fetched by index.
+/// struct Rows(Vec<&'static str>);
+/// 
+/// impl ArgumentSource for Rows {
+///     fn resolve(&self, index: usize) -> Option<Argument<'_>> {
+///         self.0.get(index).map(|row| Argument::from(*row))
+///     }
+/// }
+/// 
+/// let model: Model = "<text>#GET 1</text>".parse().unwrap();
+/// let image = model.generate(&Rows(vec!["first", "second"])).unwrap();
+/// 
+/// assert_eq!(image.content(), b"<text>second</text>");
+/// ```
+/// 
+/// A function is a source, and the arguments of the blocks that are not
+/// generated are never resolved:
+/// 
+/// ```
+/// use std::cell::RefCell;
+/// use svggen::{Model, Argument, GenerateError};
+/// 
+/// let model: Model = "<svg>\n#IF 0\n<text>#GET 1</text>\n#END\n<text>#GET 2</text>\n</svg>".parse().unwrap();
+/// 
+/// let fetched = RefCell::new(Vec::new());
+/// let source = |index: usize| {
+///     fetched.borrow_mut().push(index);
+///     match index {
+///         0 => Some(Argument::Empty),
+///         2 => Some(Argument::from(format!("row {index}"))),
+///         _ => None,
+///     }
+/// };
+/// 
+/// let image = model.generate(&source).unwrap();
+/// 
+/// assert_eq!(image.content(), b"<svg>\n<text>row 2</text>\n</svg>");
+/// assert_eq!(*fetched.borrow(), [0, 2]);
+/// 
+/// // A missing argument is an error, as with a slice
+/// let source = |index: usize| (index == 0).then_some(Argument::from("yes"));
+/// assert!(matches!(model.generate(&source), Err(GenerateError::MissingArgument { index: 1 })));
+/// 
+/// // Only the indices used by the model are kept, however large they are
+/// let model: Model = "<text>#GET 2000000000</text>".parse().unwrap();
+/// let source = |index: usize| (index == 2_000_000_000).then_some(Argument::from("far"));
+/// assert_eq!(model.generate(&source).unwrap().content(), b"<text>far</text>");
+/// 
+/// let source = |_| None;
+/// assert!(matches!(model.generate(&source), Err(GenerateError::MissingArgument { index: 2_000_000_000 })));
+/// ```
+pub trait ArgumentSource {
+    /// Returns the argument of the given index, or `None` if it is missing.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `index` - The index of the argument.
+    fn resolve(&self, index: usize) -> Option<Argument<'_>>;
+
+    /// Returns the arguments as a slice, if the source is one.
+    /// 
+    /// The arguments of a slice are used in place, instead of being resolved
+    /// one by one. Returns `None` by default.
+    fn as_slice(&self) -> Option<&[Argument<'_>]> {
+        None
+    }
+}
+
+impl ArgumentSource for [Argument<'_>] {
+    /// Returns a copy of the argument of the given index.
+    fn resolve(&self, index: usize) -> Option<Argument<'_>> {
+        self.get(index).cloned()
+    }
+
+    /// Returns the arguments, so they are used in place without being copied.
+    fn as_slice(&self) -> Option<&[Argument<'_>]> {
+        Some(self)
+    }
+}
+
+impl<const N: usize> ArgumentSource for [Argument<'_>; N] {
+    /// Returns a copy of the argument of the given index.
+    fn resolve(&self, index: usize) -> Option<Argument<'_>> {
+        self.get(index).cloned()
+    }
+
+    /// Returns the arguments, so they are used in place without being copied.
+    fn as_slice(&self) -> Option<&[Argument<'_>]> {
+        Some(self)
+    }
+}
+
+impl ArgumentSource for Vec<Argument<'_>> {
+    /// Returns a copy of the argument of the given index.
+    fn resolve(&self, index: usize) -> Option<Argument<'_>> {
+        self.get(index).cloned()
+    }
+
+    /// Returns the arguments, so they are used in place without being copied.
+    fn as_slice(&self) -> Option<&[Argument<'_>]> {
+        Some(self)
+    }
+}
+
+impl<F: Fn(usize) -> Option<Argument<'static>>> ArgumentSource for F {
+    /// Calls the function with the index.
+    fn resolve(&self, index: usize) -> Option<Argument<'_>> {
+        self(index)
+    }
+}
+
+// ========================= //
+// ==== GENERATE ERROR ===== //
+// ========================= //
+
+/// An error returned when a model fails to generate an image.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum GenerateError {
+    /// An argument referenced by the model was not given.
+    MissingArgument {
+        /// The index of the missing argument.
+        index: usize,
+    },
+
+    /// A named argument referenced by the model was not given.
+    MissingNamedArgument {
+        /// The name of the missing argument.
+        name: Box<str>,
+    },
+
+    /// An item was used outside of a repetition block.
+    MissingItem,
+
+    /// A variable used by the model is not set by any part of the model.
+    MissingVariable {
+        /// The name of the variable.
+        name: Box<str>,
+    },
+
+    /// There is no model with the given name in a `TemplateSet`.
+    UnknownTemplate {
+        /// The name of the model.
+        name: Box<str>,
+    },
+
+    /// The bytes of an argument slice are out of the range of the generated argument.
+    SliceOutOfRange {
+        /// The index of the argument.
+        index: usize,
+
+        /// The index of the first byte of the slice.
+        start: usize,
+
+        /// The index after the last byte of the slice.
+        end: usize,
+
+        /// The length of the generated argument.
+        len: usize,
+    },
+
+    /// The nested models are deeper than the maximum depth.
+    DepthExceeded {
+        /// The maximum depth that was exceeded.
+        max_depth: usize,
+    },
+
+    /// An IO error occurred while writing the image.
+    #[cfg(feature = "std")]
+    Io(io::Error),
+
+    /// The generated image is not valid UTF-8, so it cannot be written as a string.
+    Utf8(core::str::Utf8Error),
+
+    /// A formatting error occurred while writing the image to a `fmt::Write`.
+    Fmt(fmt::Error),
+}
+
+impl GenerateError {
+    /// Returns the index of the missing argument, if the error is caused by one.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::GenerateError;
+    /// 
+    /// let error = GenerateError::MissingArgument { index: 3 };
+    /// assert_eq!(error.index(), Some(3));
+    /// ```
+    pub fn index(&self) -> Option<usize> {
+        match self {
+            GenerateError::MissingArgument { index } => Some(*index),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the missing argument, if the error is caused by one.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::GenerateError;
+    /// 
+    /// let error = GenerateError::MissingNamedArgument { name: "title".into() };
+    /// assert_eq!(error.name(), Some("title"));
+    /// ```
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            GenerateError::MissingNamedArgument { name } => Some(name),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for GenerateError {
+    /// Convert an IO error into a `GenerateError`.
+    fn from(err: io::Error) -> Self {
+        GenerateError::Io(err)
+    }
+}
+
+impl fmt::Display for GenerateError {
+    /// Display a `GenerateError`.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::GenerateError;
+    /// 
+    /// let error = GenerateError::MissingArgument { index: 3 };
+    /// assert_eq!(error.to_string(), "Missing argument: 3");
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GenerateError::MissingArgument { index } => write!(f, "Missing argument: {}", index),
+            GenerateError::MissingNamedArgument { name } => write!(f, "Missing named argument: {}", name),
+            GenerateError::MissingItem => write!(f, "Missing item: #ITEM used outside of a #FOR block"),
+            GenerateError::MissingVariable { name } => write!(f, "Missing variable: {}", name),
+            GenerateError::UnknownTemplate { name } => write!(f, "Unknown template: {}", name),
+            GenerateError::SliceOutOfRange { index, start, end, len } => {
+                write!(f, "Slice out of range: {}..{} of argument {} ({} bytes)", start, end, index, len)
+            }
+            GenerateError::DepthExceeded { max_depth } => write!(f, "Maximum depth exceeded: {}", max_depth),
+            #[cfg(feature = "std")]
+            GenerateError::Io(err) => write!(f, "IO error: {}", err),
+            GenerateError::Utf8(err) => write!(f, "Invalid UTF-8: {}", err),
+            GenerateError::Fmt(err) => write!(f, "Formatting error: {}", err),
+        }
+    }
+}
+
+impl error::Error for GenerateError {
+    /// Returns the underlying IO, UTF-8 or formatting error, if any.
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "std")]
+            GenerateError::Io(err) => Some(err),
+            GenerateError::Utf8(err) => Some(err),
+            GenerateError::Fmt(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+// ========================= //
+// === VALIDATION ERROR ==== //
+// ========================= //
+
+/// An error returned when a model uses arguments that are out of range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    /// The expected number of arguments.
+    expected_arity: usize,
+
+    /// The indices that are out of range (sorted and without duplicates).
+    indices: Box<[usize]>,
+}
+
+impl ValidationError {
+    /// Returns the expected number of arguments.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, ModelPart};
+    /// 
+    /// let model = Model::from(vec![ModelPart::Argument(2)]);
+    /// 
+    /// let error = model.validate(1).unwrap_err();
+    /// assert_eq!(error.expected_arity(), 1);
+    /// ```
+    pub fn expected_arity(&self) -> usize {
+        self.expected_arity
+    }
+
+    /// Returns the indices that are out of range (sorted and without duplicates).
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, ModelPart};
+    /// 
+    /// let model = Model::from(vec![ModelPart::Argument(2), ModelPart::Argument(1)]);
+    /// 
+    /// let error = model.validate(1).unwrap_err();
+    /// assert_eq!(error.indices(), &[1, 2]);
+    /// ```
+    pub fn indices(&self) -> &[usize] {
+        &self.indices
+    }
+}
+
+impl fmt::Display for ValidationError {
+    /// Display a `ValidationError`.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, ModelPart};
+    /// 
+    /// let model = Model::from(vec![ModelPart::Argument(2), ModelPart::Argument(1)]);
+    /// 
+    /// let error = model.validate(1).unwrap_err();
+    /// assert_eq!(error.to_string(), "Arguments out of range (expected 1 arguments): 1, 2");
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Arguments out of range (expected {} arguments): ", self.expected_arity)?;
+        for (i, index) in self.indices.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", index)?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for ValidationError {}
+
+// ========================= //
+// ===== MODEL WARNING ===== //
+// ========================= //
+
+/// A possible mistake in a model, reported by `Model::lint`.
+/// 
+/// A warning is only advisory: the model can still be generated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelWarning {
+    /// A positional argument is not used, but a higher index is.
+    UnusedArgument {
+        /// The index of the unused argument.
+        index: usize,
+    },
+
+    /// A positional argument is written more than once.
+    /// 
+    /// A variable (`#SET`) names an argument that is meant to be written several times.
+    RepeatedArgument {
+        /// The index of the argument.
+        index: usize,
+
+        /// The number of references writing the argument.
+        count: usize,
+    },
+
+    /// The model starts with an argument, before any text.
+    LeadingArgument {
+        /// The index of the argument.
+        index: usize,
+    },
+}
+
+impl fmt::Display for ModelWarning {
+    /// Display a `ModelWarning`.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::ModelWarning;
+    /// 
+    /// let warning = ModelWarning::RepeatedArgument { index: 0, count: 2 };
+    /// assert_eq!(warning.to_string(), "Argument 0 is written 2 times");
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ModelWarning::UnusedArgument { index } => write!(f, "Argument {} is not used", index),
+            ModelWarning::RepeatedArgument { index, count } => write!(f, "Argument {} is written {} times", index, count),
+            ModelWarning::LeadingArgument { index } => write!(f, "Argument {} is written before any text", index),
+        }
+    }
+}
+
+// ========================= //
+// ==== STRUCTURE ERROR ==== //
+// ========================= //
+
+/// An error returned by `Model::try_from_parts` when the parts are not consistent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StructureError {
+    /// An argument index is `usize::MAX`, so the arity of the model cannot be computed.
+    IndexOverflow,
+
+    /// A `ModelPart::Item` is outside of any `ModelPart::Repetition`.
+    UnexpectedItem,
+
+    /// A `ModelPart::GetVar` uses a variable that is not set by any `ModelPart::SetVar`.
+    UndefinedVariable {
+        /// The name of the variable.
+        name: Box<str>,
+    },
+
+    /// Several `ModelPart::SetVar` set the same variable.
+    DuplicateVariable {
+        /// The name of the variable.
+        name: Box<str>,
+    },
+
+    /// A `ModelPart::ArgumentSlice` starts after its end.
+    InvalidSlice {
+        /// The index of the argument.
+        index: usize,
+
+        /// The index of the first byte of the slice.
+        start: usize,
+
+        /// The index after the last byte of the slice.
+        end: usize,
+    },
+}
+
+impl fmt::Display for StructureError {
+    /// Display a `StructureError`.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::StructureError;
+    /// 
+    /// let error = StructureError::UndefinedVariable { name: "size".into() };
+    /// assert_eq!(error.to_string(), "Undefined variable: size");
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StructureError::IndexOverflow => write!(f, "Argument index overflow: usize::MAX"),
+            StructureError::UnexpectedItem => write!(f, "Unexpected item outside of a repetition"),
+            StructureError::UndefinedVariable { name } => write!(f, "Undefined variable: {}", name),
+            StructureError::DuplicateVariable { name } => write!(f, "Duplicate variable: {}", name),
+            StructureError::InvalidSlice { index, start, end } => {
+                write!(f, "Invalid slice: {}..{} of argument {}", start, end, index)
+            }
+        }
+    }
+}
+
+impl error::Error for StructureError {}
+
+// ========================= //
+// ======= PART DIFF ======= //
+// ========================= //
+
+/// A difference between the parts of two models, reported by `Model::diff`.
+/// 
+/// The parts are compared by index, so the index is the same in both models.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartDiff {
+    /// A part is only in the other model.
+    Added {
+        /// The index of the part.
+        index: usize,
+    },
+
+    /// A part is only in the first model.
+    Removed {
+        /// The index of the part.
+        index: usize,
+    },
+
+    /// Both parts are texts, with different contents.
+    TextChanged {
+        /// The index of the parts.
+        index: usize,
+    },
+
+    /// The parts are different, and at least one of them is not a text (such as
+    /// an argument reference or a block).
+    ReferenceChanged {
+        /// The index of the parts.
+        index: usize,
+    },
+}
+
+impl fmt::Display for PartDiff {
+    /// Display a `PartDiff`.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::PartDiff;
+    /// 
+    /// assert_eq!(PartDiff::TextChanged { index: 2 }.to_string(), "Text of part 2 changed");
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PartDiff::Added { index } => write!(f, "Part {} added", index),
+            PartDiff::Removed { index } => write!(f, "Part {} removed", index),
+            PartDiff::TextChanged { index } => write!(f, "Text of part {} changed", index),
+            PartDiff::ReferenceChanged { index } => write!(f, "Part {} changed", index),
+        }
+    }
+}
+
+// ========================= //
+// ====== PARSE ERROR ====== //
+// ========================= //
+
+/// An error returned when a model fails to be parsed.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use svggen::{Model, ParseErrorKind};
+/// 
+/// let error = "<svg>\n#FOR 0\n<text/>\n</svg>\n".parse::<Model>().unwrap_err();
+/// assert_eq!(error.line(), 2);
+/// assert_eq!(error.kind(), ParseErrorKind::UnclosedBlock);
+/// assert_eq!(error.to_string(), "Line 2: Unclosed block: missing #END");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseError {
+    /// The line of the error, starting at 1.
+    line: usize,
+
+    /// The kind of error.
+    kind: ParseErrorKind,
+}
+
+impl ParseError {
+    /// Returns the line of the error, starting at 1.
+    /// 
+    /// For an unclosed block, this is the line opening the block. For an error in
+    /// an included content, this is the line of the outermost `#INCLUDE`.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Returns the kind of error.
+    pub fn kind(&self) -> ParseErrorKind {
+        self.kind
+    }
+}
+
+impl fmt::Display for ParseError {
+    /// Display a `ParseError`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Line {}: {}", self.line, self.kind)
+    }
+}
+
+impl error::Error for ParseError {}
+
+/// The kind of a `ParseError`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// A block (such as `#IF`) was not closed by an `#END`.
+    UnclosedBlock,
+
+    /// An `#END` was found outside of any block.
+    UnexpectedEnd,
+
+    /// An `#ITEM` was found outside of any `#FOR` block.
+    UnexpectedItem,
+
+    /// A `#VAR` uses a variable that is not set by any `#SET` of the model.
+    UndefinedVariable,
+
+    /// A `#SET` sets a variable that is already set by another `#SET` of the model.
+    DuplicateVariable,
+
+    /// An `#INCLUDE` includes a content that is already being included.
+    CyclicInclude,
+
+    /// A `#DEFAULT` sets the default text of an argument that already has one.
+    DuplicateDefault,
+
+    /// A `#DEFAULT` text contains a `|`, which cannot be written in a default value.
+    InvalidDefault,
+}
+
+impl fmt::Display for ParseErrorKind {
+    /// Display a `ParseErrorKind`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseErrorKind::UnclosedBlock => write!(f, "Unclosed block: missing #END"),
+            ParseErrorKind::UnexpectedEnd => write!(f, "Unexpected #END outside of a block"),
+            ParseErrorKind::UnexpectedItem => write!(f, "Unexpected #ITEM outside of a #FOR block"),
+            ParseErrorKind::UndefinedVariable => write!(f, "Undefined variable: missing #SET"),
+            ParseErrorKind::DuplicateVariable => write!(f, "Duplicate variable: already set by another #SET"),
+            ParseErrorKind::CyclicInclude => write!(f, "Cyclic #INCLUDE: the content includes itself"),
+            ParseErrorKind::DuplicateDefault => write!(f, "Duplicate default: already set by another #DEFAULT"),
+            ParseErrorKind::InvalidDefault => write!(f, "Invalid default: a #DEFAULT text cannot contain a |"),
+        }
+    }
+}
+
+// ========================= //
+// ========= MODEL ========= //
+// ========================= //
+
+/// A model used to generate images.
+/// 
+/// With the `serde` feature, models, model parts and images can be serialized
+/// so that a model does not have to be parsed again.
+/// 
+/// Without the `std` feature, a model caches its arguments and variables in a
+/// `OnceCell` instead of a `OnceLock`, so it is not `Sync`.
+/// 
+/// # Examples
+/// 
+/// ```
+/// # #[cfg(feature = "serde")] {
+/// use svggen::Model;
+/// 
+/// let model: Model = "<svg>\n#GET 0\n<text>#GET title</text>\n</svg>".parse().unwrap();
+/// 
+/// let json = serde_json::to_string(&model).unwrap();
+/// assert_eq!(serde_json::from_str::<Model>(&json).unwrap(), model);
+/// 
+/// let bytes = bincode::serialize(&model).unwrap();
+/// assert_eq!(bincode::deserialize::<Model>(&bytes).unwrap(), model);
+/// # }
+/// ```
+/// 
+/// ```
+/// # #[cfg(feature = "serde")] {
+/// use svggen::{Model, ModelPart};
+/// 
+/// let model = Model::from(vec![
+///     ModelPart::from("Hi".as_bytes()),
+///     ModelPart::Argument(0),
+///     ModelPart::NamedArgument("name".into()),
+/// ]);
+/// 
+/// assert_eq!(
+///     serde_json::to_string(&model).unwrap(),
+///     r#"{"parts":[{"text":[72,105]},{"argument":0},{"named_argument":"name"}]}"#,
+/// );
+/// # }
+/// ```
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Model {
+    /// The parts of the model.
+    parts: Box<[ModelPart]>,
+
+    /// The indices of the positional arguments used by the model, computed on first use.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    arguments: Cache<BTreeSet<usize>>,
+
+    /// The index of the argument of each variable of the model, computed on first use.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    variables: Cache<Map<Box<str>, usize>>,
+}
+
+impl Model {
+    /// The maximum depth of nested models used when generating an image.
+    pub const DEFAULT_MAX_DEPTH: usize = 64;
+
+    /// The expected number of bytes of the source of a model for each part.
+    const EXPECTED_PART_SIZE: usize = 32;
+
+    /// Returns an estimation of the number of parts of a model parsed from a source of the given size.
+    /// 
+    /// The result is meant to be given to `ModelParser::capacity` when the size of
+    /// the source is known, so the parts are not reallocated while parsing large
+    /// models and small models do not allocate too much.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `len` - The number of bytes of the source of the model.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, ModelParser};
+    /// 
+    /// // A model with 500 parts
+    /// let source: String = (0..250).map(|i| format!("<text>#GET {}", i)).collect();
+    /// 
+    /// let parser = ModelParser::new().capacity(Model::estimate_parts_capacity(source.len()));
+    /// let model = parser.parse_bytes(source.as_bytes()).unwrap();
+    /// 
+    /// assert_eq!(model.parts().len(), 500);
+    /// assert_eq!(model, source.parse().unwrap());
+    /// assert_eq!(Model::estimate_parts_capacity(0), 1);
+    /// ```
+    pub fn estimate_parts_capacity(len: usize) -> usize {
+        len / Self::EXPECTED_PART_SIZE + 1
+    }
+
+    /// Returns the parts of the model.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, ModelPart};
+    /// 
+    /// let model = Model::from(vec![
+    ///     ModelPart::from("Hello ".as_bytes()),
+    ///     ModelPart::Argument(0),
+    ///     ModelPart::from("!".as_bytes()),
+    /// ]);
+    /// 
+    /// assert_eq!(model.parts(), &[
+    ///     ModelPart::Text(b"Hello ".to_vec().into()),
+    ///     ModelPart::Argument(0),
+    ///     ModelPart::Text(b"!".to_vec().into()),
+    /// ]);
+    /// ```
+    pub fn parts(&self) -> &[ModelPart] {
+        &self.parts
+    }
+
+    /// Returns the number of bytes of the text parts of the model, including the
+    /// texts inside the blocks (each counted once).
+    /// 
+    /// The arguments, their default values and the repetitions are not counted, so
+    /// this is the size of the template itself rather than of a generated image.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::Model;
+    /// 
+    /// let model: Model = "<svg>\n<text>#GET 0</text>\n#IF 1\n<g/>\n#END\n</svg>".parse().unwrap();
+    /// assert_eq!(model.static_len(), "<svg>\n<text></text>\n<g/>\n</svg>".len());
+    /// 
+    /// let model: Model = "#GET 0".parse().unwrap();
+    /// assert_eq!(model.static_len(), 0);
+    /// ```
+    pub fn static_len(&self) -> usize {
+        let mut len = 0;
+        visit_parts(&self.parts, &mut |part| len += part.byte_len().unwrap_or(0));
+        len
+    }
+
+    /// Returns a hash of the parts of the model, which is the same on every run,
+    /// platform and version of the crate.
+    /// 
+    /// Unlike the `Hash` implementation (which depends on the `Hasher` used), this
+    /// is a 64-bit FNV-1a hash of a fixed encoding of the parts, so it can be stored
+    /// (for example to invalidate cached images when a model changes). Equal models
+    /// have the same hash.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, ModelBuilder};
+    /// 
+    /// let source = "<svg>\n#IF 1\n<text>#GET 0</text>\n#END\n</svg>";
+    /// let first: Model = source.parse().unwrap();
+    /// let second: Model = source.to_owned().parse().unwrap();
+    /// assert_eq!(first.content_hash(), second.content_hash());
+    /// 
+    /// let other: Model = "<svg>\n#IF 1\n<text>#GET 1</text>\n#END\n</svg>".parse().unwrap();
+    /// assert_ne!(first.content_hash(), other.content_hash());
+    /// 
+    /// // The hash never changes
+    /// let model = ModelBuilder::new().text("<text>".as_bytes()).arg(0).text("</text>".as_bytes()).build();
+    /// assert_eq!(model.content_hash(), 0x00d6_a153_3201_d4ff);
+    /// ```
+    pub fn content_hash(&self) -> u64 {
+        let mut hash = StableHash::new();
+        hash_parts(&self.parts, &mut hash);
+        hash.0
+    }
+
+    /// Returns the parts of the model, without cloning them.
+    /// 
+    /// The parts can be changed and given back to `Model::from` to create a new model.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, ModelPart};
+    /// 
+    /// let model: Model = "<text>#GET 0</text>\n#IF 1\n#GET 1\n#END\n".parse().unwrap();
+    /// 
+    /// let parts = model.clone().into_parts();
+    /// assert_eq!(Model::from(parts), model);
+    /// 
+    /// // Remove the conditional blocks
+    /// let parts: Vec<ModelPart> = model.into_parts().into_vec().into_iter()
+    ///     .filter(|part| !matches!(part, ModelPart::Conditional { .. }))
+    ///     .collect();
+    /// assert_eq!(Model::from(parts).to_string(), "<text>#GET 0</text>\n");
+    /// ```
+    pub fn into_parts(self) -> Box<[ModelPart]> {
+        self.parts
+    }
+
+    /// Creates a new model from the given parts, checking that they are consistent.
+    /// 
+    /// `Model::from` accepts any parts, while this rejects the parts that a model
+    /// source cannot produce: an `#ITEM` outside of a `#FOR` block, a `#VAR` without
+    /// its `#SET` (or a variable set twice), a slice starting after its end, and an
+    /// argument index of `usize::MAX` (its arity would overflow).
+    /// 
+    /// # Arguments
+    /// 
+    /// * `parts` - The parts of the model.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, ModelPart, StructureError};
+    /// 
+    /// let model = Model::try_from_parts(vec![
+    ///     ModelPart::SetVar { name: "size".into(), index: 0 },
+    ///     ModelPart::Repetition { index: 1, body: vec![ModelPart::Item, ModelPart::GetVar("size".into())].into() },
+    /// ]).unwrap();
+    /// assert_eq!(model.arity(), 2);
+    /// 
+    /// let error = Model::try_from_parts(vec![ModelPart::from("<text>".as_bytes()), ModelPart::Item]).unwrap_err();
+    /// assert_eq!(error, StructureError::UnexpectedItem);
+    /// 
+    /// let error = Model::try_from_parts(vec![ModelPart::GetVar("size".into())]).unwrap_err();
+    /// assert_eq!(error, StructureError::UndefinedVariable { name: "size".into() });
+    /// 
+    /// let error = Model::try_from_parts([ModelPart::ArgumentSlice { index: 0, start: 8, end: 2 }]).unwrap_err();
+    /// assert_eq!(error, StructureError::InvalidSlice { index: 0, start: 8, end: 2 });
+    /// 
+    /// let error = Model::try_from_parts([ModelPart::Argument(usize::MAX)]).unwrap_err();
+    /// assert_eq!(error, StructureError::IndexOverflow);
+    /// ```
+    /// 
+    /// # Errors
+    /// 
+    /// * `StructureError` - The first inconsistency found in the parts.
+    pub fn try_from_parts<T: Into<Box<[ModelPart]>>>(parts: T) -> Result<Model, StructureError> {
+        let parts = parts.into();
+        let mut variables: Set<&str> = Set::new();
+        let mut uses: Vec<&str> = Vec::new();
+        check_parts(&parts, false, &mut variables, &mut uses)?;
+        if let Some(name) = uses.into_iter().find(|name| !variables.contains(name)) {
+            return Err(StructureError::UndefinedVariable { name: name.into() });
+        }
+        Ok(Model::from(parts))
+    }
+
+    /// Returns the indices of the positional arguments used by the model.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use std::collections::BTreeSet;
+    /// use svggen::{Model, ModelPart};
+    /// 
+    /// let model = Model::from(vec![
+    ///     ModelPart::Argument(2),
+    ///     ModelPart::from(" and ".as_bytes()),
+    ///     ModelPart::Argument(0),
+    ///     ModelPart::Argument(2),
+    /// ]);
+    /// 
+    /// assert_eq!(model.arguments(), BTreeSet::from([0, 2]));
+    /// ```
+    /// 
+    /// The arguments used by conditional blocks are included:
+    /// 
+    /// ```
+    /// use std::collections::BTreeSet;
+    /// use svggen::Model;
+    /// 
+    /// let model: Model = "#IF 3\n<text>#GET 1</text>\n#END\n".parse().unwrap();
+    /// assert_eq!(model.arguments(), BTreeSet::from([1, 3]));
+    /// assert_eq!(model.arity(), 4);
+    /// ```
+    pub fn arguments(&self) -> BTreeSet<usize> {
+        self.cached_arguments().clone()
+    }
+
+    /// Returns the indices of the positional arguments used by the model, computing them once.
+    fn cached_arguments(&self) -> &BTreeSet<usize> {
+        self.arguments.get_or_init(|| {
+            let mut indices = BTreeSet::new();
+            visit_parts(&self.parts, &mut |part| match part {
+                ModelPart::Argument(index)
+                | ModelPart::ArgumentOr { index, .. }
+                | ModelPart::ArgumentSlice { index, .. }
+                | ModelPart::ArgumentLine(index)
+                | ModelPart::Conditional { index, .. }
+                | ModelPart::Repetition { index, .. }
+                | ModelPart::SetVar { index, .. } => {
+                    indices.insert(*index);
+                }
+                _ => (),
+            });
+            indices
+        })
+    }
+
+    /// Returns the index of the argument of each variable of the model, computing them once.
+    fn cached_variables(&self) -> &Map<Box<str>, usize> {
+        self.variables.get_or_init(|| {
+            let mut variables = Map::new();
+            visit_parts(&self.parts, &mut |part| {
+                if let ModelPart::SetVar { name, index } = part {
+                    variables.insert(name.clone(), *index);
+                }
+            });
+            variables
+        })
+    }
+
+    /// Returns the number of positional arguments needed by the model.
+    /// 
+    /// This is the highest index used by the model plus one, or zero if the
+    /// model does not use any positional argument.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, ModelPart};
+    /// 
+    /// let model = Model::from(vec![
+    ///     ModelPart::Argument(0),
+    ///     ModelPart::from(" and ".as_bytes()),
+    ///     ModelPart::Argument(2),
+    /// ]);
+    /// assert_eq!(model.arity(), 3);
+    /// 
+    /// let model = Model::from(vec![ModelPart::from("Hello World!".as_bytes())]);
+    /// assert_eq!(model.arity(), 0);
+    /// ```
+    /// 
+    /// The arguments are computed once, the first time they are needed, whatever
+    /// the way the model was created:
+    /// 
+    /// ```
+    /// use svggen::{Model, ModelPart, ModelBuilder, Argument};
+    /// 
+    /// // Computes the arity by walking the parts
+    /// fn walk(parts: &[ModelPart]) -> usize {
+    ///     parts.iter().map(|part| match part {
+    ///         ModelPart::Argument(index) | ModelPart::ArgumentOr { index, .. } => index + 1,
+    ///         ModelPart::Conditional { index, body } | ModelPart::Repetition { index, body } => (index + 1).max(walk(body)),
+    ///         _ => 0,
+    ///     }).max().unwrap_or(0)
+    /// }
+    /// 
+    /// let parsed: Model = "#GET 1\n#IF 4\n#GET 2\n#END\n".parse().unwrap();
+    /// let models = [
+    ///     Model::from(vec![ModelPart::Argument(3)]),
+    ///     ModelBuilder::new().arg(5).text("x".as_bytes()).build(),
+    ///     parsed.clone(),
+    ///     parsed.clone().compile(),
+    ///     parsed.clone().map_arguments(|index| index * 2),
+    ///     Model::concat(&[parsed.clone(), parsed.clone()]).0,
+    ///     parsed.bind(&[None, Some(Argument::Empty)]).unwrap(),
+    /// ];
+    /// 
+    /// for model in models {
+    ///     assert_eq!(model.arity(), walk(model.parts()));
+    ///     assert_eq!(model.clone().arity(), model.arity());
+    /// }
+    /// 
+    /// // The parser keeps the index `usize::MAX` as text, and the arity saturates
+    /// let parsed: Model = "<text>#GET 18446744073709551615</text>".parse().unwrap();
+    /// assert_eq!(parsed.arity(), 0);
+    /// assert_eq!(parsed.generate(&[]).unwrap().content(), b"<text>#GET 18446744073709551615</text>");
+    /// assert_eq!(Model::from(vec![ModelPart::Argument(usize::MAX)]).arity(), usize::MAX);
+    /// ```
+    pub fn arity(&self) -> usize {
+        self.cached_arguments().last().map_or(0, |index| index.saturating_add(1))
+    }
+
+    /// Returns how many of the given arguments are used by the model.
+    /// 
+    /// An argument is used if its index is referenced by at least one part of the
+    /// model (see `Model::arguments`), even if the part is never generated.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `args` - The arguments to check.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, Argument};
+    /// 
+    /// let model: Model = "<text>#GET 0 #GET 2</text>".parse().unwrap();
+    /// let args = [Argument::number(1.0), Argument::number(2.0), Argument::number(3.0), Argument::number(4.0)];
+    /// 
+    /// // The arguments 1 and 3 are never referenced
+    /// assert_eq!(model.count_arguments_used(&args), 2);
+    /// assert!(!model.uses_all(&args));
+    /// assert!(model.uses_all(&args[..1]));
+    /// ```
+    pub fn count_arguments_used(&self, args: &[Argument]) -> usize {
+        self.cached_arguments().range(..args.len()).count()
+    }
+
+    /// Returns `true` if every given argument is used by the model (see `Model::count_arguments_used`).
+    /// 
+    /// # Arguments
+    /// 
+    /// * `args` - The arguments to check.
+    pub fn uses_all(&self, args: &[Argument]) -> bool {
+        self.count_arguments_used(args) == args.len()
+    }
+
+    /// Returns `true` if the argument of the given index is used by the model.
+    /// 
+    /// As with `Model::count_arguments_used`, the argument is used if at least one
+    /// part of the model references it, even if the part is never generated. The
+    /// indices used are computed once, so this does not scan the model again.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `index` - The index of the argument.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::Model;
+    /// 
+    /// let model: Model = "<svg>\n#IF 3\n<text>#GET 0</text>\n#END\n</svg>".parse().unwrap();
+    /// 
+    /// assert!(model.is_parameterized_at(0));
+    /// assert!(model.is_parameterized_at(3));
+    /// assert!(!model.is_parameterized_at(5));
+    /// ```
+    pub fn is_parameterized_at(&self, index: usize) -> bool {
+        self.cached_arguments().contains(&index)
+    }
+
+    /// Returns `true` if the model only contains text, so it does not use any argument.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::Model;
+    /// 
+    /// let model: Model = "<svg>\n<rect/>\n</svg>".parse().unwrap();
+    /// assert!(model.is_static());
+    /// 
+    /// let model: Model = "<svg>\n#GET 0\n</svg>".parse().unwrap();
+    /// assert!(!model.is_static());
+    /// ```
+    pub fn is_static(&self) -> bool {
+        self.parts.iter().all(|part| matches!(part, ModelPart::Text(_)))
+    }
+
+    /// Returns the image of a static model, without any argument.
+    /// 
+    /// Returns `None` if the model uses arguments (see `Model::is_static`).
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::Model;
+    /// 
+    /// let model: Model = "<svg>\n<rect/>\n</svg>".parse().unwrap();
+    /// assert_eq!(model.to_image().unwrap().content(), b"<svg>\n<rect/>\n</svg>");
+    /// 
+    /// let model: Model = "<svg>\n#GET 0|<rect/>|\n</svg>".parse().unwrap();
+    /// assert_eq!(model.to_image(), None);
+    /// ```
+    pub fn to_image(&self) -> Option<Image> {
+        if !self.is_static() {
+            return None;
+        }
+        let content: Vec<u8> = self.parts.iter()
+            .flat_map(|part| match part {
+                ModelPart::Text(text) => text.iter().copied(),
+                _ => unreachable!("a static model only contains text"),
+            })
+            .collect();
+        Some(Image { content: content.into() })
+    }
+
+    /// Checks that every positional argument used by the model is in range.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `expected_arity` - The number of arguments that will be given to the model.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, ModelPart};
+    /// 
+    /// let model = Model::from(vec![
+    ///     ModelPart::Argument(0),
+    ///     ModelPart::from(" and ".as_bytes()),
+    ///     ModelPart::Argument(99),
+    /// ]);
+    /// 
+    /// assert!(model.validate(100).is_ok());
+    /// 
+    /// let error = model.validate(2).unwrap_err();
+    /// assert_eq!(error.indices(), &[99]);
+    /// ```
+    /// 
+    /// # Errors
+    /// 
+    /// * `ValidationError` - Some arguments are out of range. All of them are reported.
+    pub fn validate(&self, expected_arity: usize) -> Result<(), ValidationError> {
+        let indices: Box<[usize]> = self.cached_arguments().range(expected_arity..).copied().collect();
+        if indices.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationError { expected_arity, indices })
+        }
+    }
+
+    /// Compares the top-level parts of the model with the parts of another model.
+    /// 
+    /// The parts are compared by index: a part inserted in the middle of a model
+    /// changes all the following parts. A block is compared as a whole, so a change
+    /// inside a block is a `PartDiff::ReferenceChanged` of the block. The models are
+    /// compared as they are, so compiling both models first (see `Model::compile`)
+    /// ignores how their texts are split.
+    /// 
+    /// Returns the differences sorted by index, empty if the models are equal.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `other` - The model to compare with.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, PartDiff};
+    /// 
+    /// let old: Model = "<text fill=\"red\">#GET 0</text>".parse().unwrap();
+    /// let new: Model = "<text fill=\"blue\">#GET 0</text>".parse().unwrap();
+    /// assert_eq!(old.diff(&new), [PartDiff::TextChanged { index: 0 }]);
+    /// assert!(old.diff(&old).is_empty());
+    /// 
+    /// let new: Model = "<text fill=\"red\">#GET 1</text><g/>#GET 2".parse().unwrap();
+    /// assert_eq!(old.diff(&new), [
+    ///     PartDiff::ReferenceChanged { index: 1 },
+    ///     PartDiff::TextChanged { index: 2 },
+    ///     PartDiff::Added { index: 3 },
+    /// ]);
+    /// assert_eq!(new.diff(&old)[2], PartDiff::Removed { index: 3 });
+    /// ```
+    pub fn diff(&self, other: &Model) -> Vec<PartDiff> {
+        (0..self.parts.len().max(other.parts.len())).filter_map(|index| {
+            match (self.parts.get(index), other.parts.get(index)) {
+                (Some(part), Some(other)) if part == other => None,
+                (Some(ModelPart::Text(_)), Some(ModelPart::Text(_))) => Some(PartDiff::TextChanged { index }),
+                (Some(_), Some(_)) => Some(PartDiff::ReferenceChanged { index }),
+                (Some(_), None) => Some(PartDiff::Removed { index }),
+                (None, _) => Some(PartDiff::Added { index }),
+            }
+        }).collect()
+    }
+
+    /// Returns the possible mistakes of the model.
+    /// 
+    /// The warnings are only advisory, and are sorted by kind then by index:
+    /// 
+    /// * `ModelWarning::UnusedArgument` - An index below `Model::arity` is not used.
+    /// * `ModelWarning::RepeatedArgument` - An argument is written by more than one
+    ///   `#GET` (the `#IF` and `#FOR` blocks do not write their argument).
+    /// * `ModelWarning::LeadingArgument` - The first part of the model is an argument.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, ModelWarning};
+    /// 
+    /// let model: Model = "<text>#GET 0</text>\n<text>#GET 0|none|</text>\n#IF 2\n#GET 2\n#END\n".parse().unwrap();
+    /// 
+    /// assert_eq!(model.lint(), [
+    ///     ModelWarning::UnusedArgument { index: 1 },
+    ///     ModelWarning::RepeatedArgument { index: 0, count: 2 },
+    /// ]);
+    /// 
+    /// let model: Model = "#GET 0<text>#GET 1</text>".parse().unwrap();
+    /// assert_eq!(model.lint(), [ModelWarning::LeadingArgument { index: 0 }]);
+    /// ```
+    pub fn lint(&self) -> Vec<ModelWarning> {
+        let arguments = self.cached_arguments();
+        let mut warnings: Vec<ModelWarning> = (0..self.arity())
+            .filter(|index| !arguments.contains(index))
+            .map(|index| ModelWarning::UnusedArgument { index })
+            .collect();
+
+        // Count the references writing each argument
+        let mut counts: BTreeMap<usize, usize> = BTreeMap::new();
+        visit_parts(&self.parts, &mut |part| {
+            if let ModelPart::Argument(index)
+                | ModelPart::ArgumentOr { index, .. }
+                | ModelPart::ArgumentSlice { index, .. }
+                | ModelPart::ArgumentLine(index) = part
+            {
+                *counts.entry(*index).or_default() += 1;
+            }
+        });
+        warnings.extend(counts.into_iter()
+            .filter(|(_, count)| *count > 1)
+            .map(|(index, count)| ModelWarning::RepeatedArgument { index, count }));
+
+        if let Some(
+            ModelPart::Argument(index)
+            | ModelPart::ArgumentOr { index, .. }
+            | ModelPart::ArgumentSlice { index, .. }
+            | ModelPart::ArgumentLine(index)
+        ) = self.parts.first() {
+            warnings.push(ModelWarning::LeadingArgument { index: *index });
+        }
+        warnings
+    }
+
+    /// Merges the adjacent text parts of the model, so it is faster to generate.
+    /// 
+    /// Empty text parts are removed. The other parts and their order are unchanged,
+    /// so the generated images are the same.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, ModelPart, Argument};
+    /// 
+    /// let model = Model::from(vec![
+    ///     ModelPart::from("<svg>".as_bytes()),
+    ///     ModelPart::from("<text>".as_bytes()),
+    ///     ModelPart::Argument(0),
+    ///     ModelPart::from("</text>".as_bytes()),
+    ///     ModelPart::from("</svg>".as_bytes()),
+    /// ]);
+    /// let args = [Argument::from("Hello".as_bytes())];
+    /// let image = model.generate(&args).unwrap();
+    /// 
+    /// let compiled = model.compile();
+    /// assert_eq!(compiled.parts(), &[
+    ///     ModelPart::Text(b"<svg><text>".to_vec().into()),
+    ///     ModelPart::Argument(0),
+    ///     ModelPart::Text(b"</text></svg>".to_vec().into()),
+    /// ]);
+    /// assert_eq!(compiled.generate(&args).unwrap(), image);
+    /// ```
+    pub fn compile(self) -> Model {
+        Model::from(compile_parts(self.parts))
+    }
+
+    /// Prepares the model for fast generation.
+    /// 
+    /// The `CompiledModel` generates the same images, but it writes all the texts
+    /// from a single buffer instead of going through each text part.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, Argument};
+    /// 
+    /// let model: Model = "<rect width=\"#GET 0\" height=\"#GET 1\"/>".parse().unwrap();
+    /// let args = [Argument::number(10.0), Argument::number(20.0)];
+    /// 
+    /// let image = model.precompiled().generate(&args).unwrap();
+    /// assert_eq!(image.content(), b"<rect width=\"10\" height=\"20\"/>");
+    /// assert_eq!(image, model.generate(&args).unwrap());
+    /// ```
+    pub fn precompiled(&self) -> CompiledModel {
+        CompiledModel::from(self)
+    }
+
+    /// Removes the XML comments (`<!-- ... -->`) from the texts of the model.
+    /// 
+    /// The texts are scanned as XML, so a `<!--` inside an attribute value or a
+    /// CDATA section is kept. A comment can span several parts: the arguments
+    /// written inside it are removed with it (and the variables set inside it are
+    /// kept, as they write nothing). The adjacent texts are merged as with
+    /// `Model::compile`.
+    /// 
+    /// The arguments are assumed not to open or close a comment, a tag or an
+    /// attribute value. A comment is kept if it is not closed in the same block,
+    /// or if it contains a block (as the block could close it).
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, ModelPart, Argument};
+    /// 
+    /// let model: Model = "<svg><!-- Made by #GET 0 --><text>#GET 1</text></svg>".parse().unwrap();
+    /// assert_eq!(model.strip_comments().parts(), &[
+    ///     ModelPart::Text(b"<svg><text>".to_vec().into()),
+    ///     ModelPart::Argument(1),
+    ///     ModelPart::Text(b"</text></svg>".to_vec().into()),
+    /// ]);
+    /// 
+    /// // Attribute values and CDATA sections are kept
+    /// let source = "<g data-note=\"<!-- a -->\"><style><![CDATA[<!-- b -->]]></style><!-- c --></g>";
+    /// let model: Model = source.parse().unwrap();
+    /// let image = model.strip_comments().generate(&[]).unwrap();
+    /// assert_eq!(image.content(), b"<g data-note=\"<!-- a -->\"><style><![CDATA[<!-- b -->]]></style></g>");
+    /// 
+    /// // A comment containing a block is kept
+    /// let model: Model = "<g><!--\n#IF 0\n-->\n#END\n--></g>".parse().unwrap();
+    /// assert_eq!(model.clone().strip_comments(), model);
+    /// ```
+    pub fn strip_comments(self) -> Model {
+        Model::from(strip_comment_parts(self.parts))
+    }
+
+    /// Normalizes the whitespace of the texts of the model.
+    /// 
+    /// The spaces and tabs at the end of each line are removed, and each run of
+    /// blank lines is replaced by a single blank line. The adjacent texts are
+    /// merged as with `Model::compile`.
+    /// 
+    /// The content of the elements where whitespace is significant (`<text>`,
+    /// `<tspan>`, `<textPath>`, `<style>`, `<script>`, `<title>` and `<desc>`), the
+    /// attribute values, the comments, the CDATA sections and the processing
+    /// instructions are kept as is. The spaces before an argument or a block are
+    /// kept too, and the arguments are assumed not to open or close a tag.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::Model;
+    /// 
+    /// let source = "<svg>  \n\t<rect/>\t\n\n\n\n\t<text>a  \n\n\n  b</text>  \n</svg>";
+    /// let model: Model = source.parse().unwrap();
+    /// 
+    /// let image = model.normalize_whitespace().generate(&[]).unwrap();
+    /// assert_eq!(image.content(), b"<svg>\n\t<rect/>\n\n\t<text>a  \n\n\n  b</text>\n</svg>");
+    /// ```
+    /// 
+    /// The arguments and the blocks are not affected:
+    /// 
+    /// ```
+    /// use svggen::{Model, Argument};
+    /// 
+    /// let model: Model = "<g>\n#FOR 0\n  <rect width=\"#ITEM  \"/>   \n#END\n</g>  ".parse().unwrap();
+    /// let args = [Argument::List(vec![Argument::from("1"), Argument::from("2")])];
+    /// 
+    /// let image = model.normalize_whitespace().generate(&args).unwrap();
+    /// assert_eq!(image.content(), b"<g>\n  <rect width=\"1  \"/>\n  <rect width=\"2  \"/>\n</g>  ");
+    /// ```
+    pub fn normalize_whitespace(self) -> Model {
+        Model::from(normalize_whitespace_parts(self.parts, &mut WhitespaceScan::default()))
+    }
+
+    /// Changes the index of every positional argument used by the model.
+    /// 
+    /// The indices of the arguments, of their default values and of the blocks
+    /// are rewritten with `f`. The named arguments are unchanged.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `f` - The function giving the new index of each index.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use std::collections::BTreeSet;
+    /// use svggen::{Model, Argument};
+    /// 
+    /// let model: Model = "<text>#GET 0 #GET 2|none|</text>\n#IF 1\n#GET name\n#END\n".parse().unwrap();
+    /// let shifted = model.map_arguments(|index| index + 10);
+    /// 
+    /// assert_eq!(shifted.arguments(), BTreeSet::from([10, 11, 12]));
+    /// assert_eq!(shifted.to_string(), "<text>#GET 10 #GET 12|none|</text>\n#IF 11\n#GET name\n#END\n");
+    /// ```
+    pub fn map_arguments<F: Fn(usize) -> usize>(self, f: F) -> Model {
+        Model::from(map_parts(self.parts, &f))
+    }
+
+    /// Combines multiple models into one, by appending their parts end to end.
+    /// 
+    /// Nothing is inserted between the models. The positional arguments of each model
+    /// are offset so they do not overlap: the arguments of a model start right after
+    /// the arguments of the previous one (see `Model::arity`). The named arguments are
+    /// not renamed, so they are shared by all the models. A variable set by a model
+    /// keeps its name, unless a previous model sets a variable with the same name:
+    /// it is then renamed `name_n` (with the smallest `n` giving a new name), so each
+    /// model keeps its own variables.
+    /// 
+    /// Returns the combined model and the range of argument indices given to each model.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `models` - The models to combine.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, Argument};
+    /// 
+    /// let header: Model = "<text>#GET 0 #GET 1</text>".parse().unwrap();
+    /// let footer: Model = "<text>#GET 1 #GET 0</text>".parse().unwrap();
+    /// 
+    /// let (model, ranges) = Model::concat(&[header, footer]);
+    /// assert_eq!(&*ranges, &[0..2, 2..4]);
+    /// assert_eq!(model.to_string(), "<text>#GET 0 #GET 1</text><text>#GET 3 #GET 2</text>");
+    /// 
+    /// let args: Vec<Argument> = ["a", "b", "c", "d"].iter().map(|arg| Argument::from(arg.as_bytes())).collect();
+    /// let image = model.generate(&args).unwrap();
+    /// assert_eq!(image.content(), b"<text>a b</text><text>d c</text>");
+    /// ```
+    /// 
+    /// The variables set by several models are renamed:
+    /// 
+    /// ```
+    /// use svggen::{Model, Argument};
+    /// 
+    /// let fill: Model = "#SET c = 0\n<g fill=\"#VAR c\"/>".parse().unwrap();
+    /// let stroke: Model = "#SET c = 0\n<g stroke=\"#VAR c\"/>".parse().unwrap();
+    /// 
+    /// let (model, _) = Model::concat(&[fill, stroke]);
+    /// assert_eq!(model.to_string(), "#SET c = 0\n<g fill=\"#VAR c\"/>\n#SET c_1 = 1\n<g stroke=\"#VAR c_1\"/>");
+    /// assert!(model.to_string().parse::<Model>().is_ok());
+    /// assert!(Model::try_from_parts(model.parts().to_vec()).is_ok());
+    /// 
+    /// let image = model.generate(&[Argument::from("red"), Argument::from("blue")]).unwrap();
+    /// assert_eq!(image.content(), b"<g fill=\"red\"/><g stroke=\"blue\"/>");
+    /// ```
+    pub fn concat(models: &[Model]) -> (Model, Box<[Range<usize>]>) {
+        let mut parts: Vec<ModelPart> = Vec::new();
+        let mut ranges: Vec<Range<usize>> = Vec::with_capacity(models.len());
+        let mut variables: Set<Box<str>> = Set::new();
+        let mut offset = 0;
+        for model in models {
+            // Rename the variables already set by a previous model
+            let mut names: Vec<&Box<str>> = model.cached_variables().keys().collect();
+            names.sort();
+            let mut renamed: Map<Box<str>, Box<str>> = Map::new();
+            for name in names {
+                if variables.contains(name) {
+                    let new_name = (1..)
+                        .map(|n| format!("{}_{}", name, n).into_boxed_str())
+                        .find(|new_name| !variables.contains(new_name) && !model.cached_variables().contains_key(new_name))
+                        .expect("there is always an unused name");
+                    renamed.insert(name.clone(), new_name);
+                }
+            }
+            variables.extend(model.cached_variables().keys().map(|name| renamed.get(name).unwrap_or(name).clone()));
+
+            let arity = model.arity();
+            let model_parts = rename_variable_parts(model.parts.clone(), &renamed);
+            parts.extend(map_parts(model_parts, &|index| index + offset).into_vec());
+            ranges.push(offset..offset + arity);
+            offset += arity;
+        }
+        (Model::from(parts), ranges.into())
+    }
+
+    /// Splits the model around the first reference to a positional argument.
+    /// 
+    /// Returns the model of the parts before the first `ModelPart::Argument(index)`,
+    /// and the model of the parts after it. With `keep`, the reference itself starts
+    /// the second model, otherwise it is dropped. Only the parts outside of the blocks
+    /// are searched, since a block cannot be split. Returns `None` if there is no
+    /// such reference.
+    /// 
+    /// The models keep the same indices, so they can be generated with the same
+    /// arguments, for example to write the start of an image before the argument is
+    /// computed.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `index` - The index of the argument to split the model around.
+    /// * `keep` - Whether the reference starts the second model.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::Model;
+    /// 
+    /// let model: Model = "Hello #GET 0 !".parse().unwrap();
+    /// 
+    /// let (before, after) = model.split_at_argument(0, false).unwrap();
+    /// assert_eq!(before.to_string(), "Hello ");
+    /// assert_eq!(after.to_string(), " !");
+    /// 
+    /// let (before, after) = model.split_at_argument(0, true).unwrap();
+    /// assert_eq!(before.to_string(), "Hello ");
+    /// assert_eq!(after.to_string(), "#GET 0 !");
+    /// 
+    /// assert_eq!(model.split_at_argument(1, false), None);
+    /// 
+    /// // A reference inside a block is not used
+    /// let model: Model = "#IF 1\n#GET 0\n#END\n<g>#GET 0</g>".parse().unwrap();
+    /// let (before, after) = model.split_at_argument(0, false).unwrap();
+    /// assert_eq!(before.to_string(), "#IF 1\n#GET 0\n#END\n<g>");
+    /// assert_eq!(after.to_string(), "</g>");
+    /// ```
+    pub fn split_at_argument(&self, index: usize, keep: bool) -> Option<(Model, Model)> {
+        let position = self.parts.iter().position(|part| *part == ModelPart::Argument(index))?;
+        let start = if keep { position } else { position + 1 };
+        Some((Model::from(&self.parts[..position]), Model::from(&self.parts[start..])))
+    }
+
+    /// Creates a new model with some positional arguments already given.
+    /// 
+    /// Every argument given as `Some` is generated once and written as text, and
+    /// the blocks using it are expanded. The arguments given as `None`, and the
+    /// arguments after the end of `args`, are kept as arguments of the new model.
+    /// They are reindexed to be contiguous, in the same order. The named arguments
+    /// are unchanged.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `args` - The arguments to give, or `None` to keep an argument.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, Argument};
+    /// 
+    /// let model: Model = "<text>#GET 0</text><text>#GET 1</text>".parse().unwrap();
+    /// 
+    /// let bound = model.bind(&[Some(Argument::from("Header".as_bytes()))]).unwrap();
+    /// assert_eq!(bound.to_string(), "<text>Header</text><text>#GET 0</text>");
+    /// 
+    /// let image = bound.generate(&[Argument::from("Body".as_bytes())]).unwrap();
+    /// assert_eq!(image.content(), b"<text>Header</text><text>Body</text>");
+    /// ```
+    /// 
+    /// The blocks using a given argument are expanded:
+    /// 
+    /// ```
+    /// use svggen::{Model, Argument};
+    /// 
+    /// let model: Model = "#IF 0\n<title/>\n#END\n#FOR 1\n<text>#ITEM #GET 2</text>\n#END\n".parse().unwrap();
+    /// let items = Argument::List(vec![Argument::from("a".as_bytes()), Argument::from("b".as_bytes())]);
+    /// 
+    /// let bound = model.bind(&[Some(Argument::Empty), Some(items)]).unwrap();
+    /// assert_eq!(bound.to_string(), "<text>a #GET 0</text>\n<text>b #GET 0</text>\n");
+    /// ```
+    /// 
+    /// # Errors
+    /// 
+    /// * `GenerateError::MissingArgument` - A model given as an argument needs an argument that was not given.
+    /// * `GenerateError::DepthExceeded` - The nested models are deeper than `Model::DEFAULT_MAX_DEPTH`.
+    pub fn bind(&self, args: &[Option<Argument>]) -> Result<Model, GenerateError> {
+        let mut parts: Vec<ModelPart> = Vec::with_capacity(self.parts.len());
+        bind_parts(&self.parts, args, self.cached_variables(), None, &mut parts)?;
+        Ok(Model::from(compile_parts(parts.into())))
+    }
+
+    /// Creates a new model with one positional argument written as text.
+    /// 
+    /// The argument is generated once and written as text, and the blocks using it
+    /// are expanded, as with `Model::bind`. Unlike `Model::bind`, the other arguments
+    /// keep their index, so the index of the replaced argument is just not used anymore.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `index` - The index of the argument to replace.
+    /// * `value` - The argument written in place of the references to `index`.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use std::collections::BTreeSet;
+    /// use svggen::{Model, Argument};
+    /// 
+    /// let model: Model = "<text>#GET 0</text>#GET 1<text>#GET 2</text>".parse().unwrap();
+    /// 
+    /// let replaced = model.replace_argument(1, Argument::from("<logo/>".as_bytes())).unwrap();
+    /// assert_eq!(replaced.to_string(), "<text>#GET 0</text><logo/><text>#GET 2</text>");
+    /// assert_eq!(replaced.arguments(), BTreeSet::from([0, 2]));
+    /// 
+    /// let args = [Argument::from("a".as_bytes()), Argument::Empty, Argument::from("c".as_bytes())];
+    /// let image = replaced.generate(&args).unwrap();
+    /// assert_eq!(image.content(), b"<text>a</text><logo/><text>c</text>");
+    /// ```
+    /// 
+    /// # Errors
+    /// 
+    /// * `GenerateError::MissingArgument` - The value is a model that needs an argument that was not given.
+    /// * `GenerateError::DepthExceeded` - The nested models are deeper than `Model::DEFAULT_MAX_DEPTH`.
+    pub fn replace_argument(self, index: usize, value: Argument) -> Result<Model, GenerateError> {
+        let mut args = vec![None; index];
+        args.push(Some(value));
+        let bound = self.bind(&args)?;
+
+        // Give back their index to the arguments after the replaced one
+        Ok(bound.map_arguments(|i| if i < index { i } else { i + 1 }))
+    }
+
+    /// Write the model to a writer.
+    /// 
+    /// The arguments are usually a slice, but any `ArgumentSource` can give them,
+    /// resolving them only when needed.
+    /// 
+    /// Requires the `std` feature.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `writer` - The writer to write the model to.
+    /// * `args` - The arguments to use.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use rutil::read::Readable;
+    /// use svggen::{Model, ModelPart, Image, Argument};
+    /// 
+    /// let model = Model::from(vec![
+    ///     ModelPart::from("Hello ".as_bytes()),
+    ///     ModelPart::Argument(0),
+    ///     ModelPart::from("!".as_bytes()),
+    /// ]);
+    /// 
+    /// let image = Image::from("World".as_bytes());
+    /// let args = [Argument::Image(&image)];
+    /// 
+    /// let mut buffer: Vec<u8> = Vec::new();
+    /// 
+    /// // buffer implements `io::Write` so we can use it as a writer
+    /// model.write(&mut buffer, &args).unwrap();
+    /// 
+    /// assert_eq!(buffer, b"Hello World!");
+    /// ```
+    /// 
+    /// # Errors
+    /// 
+    /// * `GenerateError::MissingArgument` - An argument referenced by the model was not given.
+    /// * `GenerateError::DepthExceeded` - The nested models are deeper than `Model::DEFAULT_MAX_DEPTH`.
+    /// * `GenerateError::Io` - An IO error occurred while writing.
+    /// 
+    /// ```
+    /// use svggen::{Model, ModelPart, GenerateError};
+    /// 
+    /// let model = Model::from(vec![ModelPart::Argument(1)]);
+    /// let mut buffer: Vec<u8> = Vec::new();
+    /// 
+    /// let error = model.write(&mut buffer, &[]).unwrap_err();
+    /// assert!(matches!(error, GenerateError::MissingArgument { index: 1 }));
+    /// 
+    /// // The same error as `Model::generate`, with the index of the missing argument
+    /// assert_eq!(error.index(), Some(1));
+    /// assert_eq!(error.index(), model.generate(&[]).unwrap_err().index());
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn write<W: io::Write, S: ArgumentSource + ?Sized>(&self, writer: &mut W, args: &S) -> Result<(), GenerateError> {
+        match args.as_slice() {
+            Some(args) => self.generate_to(writer, args),
+            None => self.write_resolved(writer, args),
+        }
+    }
+
+    /// Write the model to a writer, returning the number of bytes written.
+    /// 
+    /// This is `Model::write` for metrics and logging: the count is the length of
+    /// the generated image, as given by `Image::len` with `Model::generate`.
+    /// 
+    /// Requires the `std` feature.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `writer` - The writer to write the model to.
+    /// * `args` - The arguments to use.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, Argument};
+    /// 
+    /// let model: Model = "<svg>\n#FOR 0\n<text>#ITEM</text>\n#END\n</svg>".parse().unwrap();
+    /// let args = [Argument::List(vec![Argument::from("Hello"), Argument::from("World")])];
+    /// 
+    /// let mut buffer: Vec<u8> = Vec::new();
+    /// let written = model.write_counted(&mut buffer, &args).unwrap();
+    /// 
+    /// assert_eq!(written, buffer.len());
+    /// assert_eq!(written, model.generate(&args).unwrap().len());
+    /// ```
+    /// 
+    /// # Errors
+    /// 
+    /// * `GenerateError::MissingArgument` - An argument referenced by the model was not given.
+    /// * `GenerateError::DepthExceeded` - The nested models are deeper than `Model::DEFAULT_MAX_DEPTH`.
+    /// * `GenerateError::Io` - An IO error occurred while writing.
+    #[cfg(feature = "std")]
+    pub fn write_counted<W: io::Write>(&self, writer: &mut W, args: &[Argument]) -> Result<usize, GenerateError> {
+        let mut writer = CountingWriter { writer, written: 0 };
+        self.generate_to(&mut writer, args)?;
+        Ok(writer.written)
+    }
+
+    /// Write the model to a writer, reindented for reading (see `Image::pretty`).
+    /// 
+    /// The image is generated in memory and reindented before being written. This
+    /// is meant for inspecting the generated images, and is best-effort for
+    /// well-formed content.
+    /// 
+    /// Requires the `std` feature.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `writer` - The writer to write the model to.
+    /// * `args` - The arguments to use.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, Argument};
+    /// 
+    /// let model: Model = "<svg><g>#GET 0</g></svg>".parse().unwrap();
+    /// let mut buffer: Vec<u8> = Vec::new();
+    /// model.write_pretty(&mut buffer, &[Argument::from("<rect/><text>Hi</text>")]).unwrap();
+    /// 
+    /// assert_eq!(buffer, b"<svg>\n  <g>\n    <rect/>\n    <text>Hi</text>\n  </g>\n</svg>\n");
+    /// ```
+    /// 
+    /// # Errors
+    /// 
+    /// * `GenerateError::MissingArgument` - An argument referenced by the model was not given.
+    /// * `GenerateError::DepthExceeded` - The nested models are deeper than `Model::DEFAULT_MAX_DEPTH`.
+    /// * `GenerateError::Io` - An IO error occurred while writing.
+    #[cfg(feature = "std")]
+    pub fn write_pretty<W: io::Write>(&self, writer: &mut W, args: &[Argument]) -> Result<(), GenerateError> {
+        writer.write_all(self.generate(args)?.pretty().content())?;
+        Ok(())
+    }
+
+    /// Generates the image directly into a writer, without buffering it.
+    /// 
+    /// This is the streaming way to generate an image: each part of the model is
+    /// written as soon as it is reached. `Model::write` uses this method when its
+    /// arguments are a slice, and `Model::generate` writes the parts the same way
+    /// into a buffer.
+    /// 
+    /// Requires the `std` feature.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `writer` - The writer to write the image to.
+    /// * `args` - The arguments to use.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, Argument};
+    /// 
+    /// let model: Model = "<text>#GET 0</text>".parse().unwrap();
+    /// 
+    /// let mut buffer: Vec<u8> = Vec::new();
+    /// model.generate_to(&mut buffer, &[Argument::from("Hello".as_bytes())]).unwrap();
+    /// 
+    /// assert_eq!(buffer, b"<text>Hello</text>");
+    /// ```
+    /// 
+    /// # Errors
+    /// 
+    /// * `GenerateError::MissingArgument` - An argument referenced by the model was not given.
+    /// * `GenerateError::DepthExceeded` - The nested models are deeper than `Model::DEFAULT_MAX_DEPTH`.
+    /// * `GenerateError::Io` - An IO error occurred while writing.
+    #[cfg(feature = "std")]
+    pub fn generate_to<W: io::Write>(&self, writer: &mut W, args: &[Argument]) -> Result<(), GenerateError> {
+        write_parts(&self.parts, writer, Context::new(args, &Map::new(), self.cached_variables(), Self::DEFAULT_MAX_DEPTH))
+    }
+
+    /// Write the model to a writer, flushing it regularly.
+    /// 
+    /// `Model::write` never flushes the writer, so a buffered writer (such as an
+    /// `io::BufWriter` over a socket) can keep a large image until the end. This
+    /// method flushes the writer each time at least `flush_every` bytes have been
+    /// written since the last flush, and once more at the end.
+    /// 
+    /// Requires the `std` feature.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `writer` - The writer to write the model to.
+    /// * `args` - The arguments to use.
+    /// * `flush_every` - The number of bytes written between two flushes.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use std::io;
+    /// use svggen::{Model, Argument};
+    /// 
+    /// // A writer counting its flushes
+    /// #[derive(Default)]
+    /// struct Counter {
+    ///     content: Vec<u8>,
+    ///     flushes: usize,
+    /// }
+    /// 
+    /// impl io::Write for Counter {
+    ///     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    ///         self.content.write(buf)
+    ///     }
+    /// 
+    ///     fn flush(&mut self) -> io::Result<()> {
+    ///         self.flushes += 1;
+    ///         Ok(())
+    ///     }
+    /// }
+    /// 
+    /// let model: Model = "#FOR 0\n<rect/>\n#END\n".parse().unwrap();
+    /// let args = [Argument::List(vec![Argument::Empty; 100])];
+    /// 
+    /// // 100 rectangles of 8 bytes, flushed every 80 bytes and at the end
+    /// let mut counter = Counter::default();
+    /// model.write_buffered(&mut counter, &args, 80).unwrap();
+    /// assert_eq!(counter.content, model.generate(&args).unwrap().content());
+    /// assert_eq!(counter.flushes, 11);
+    /// 
+    /// // `Model::write` does not flush
+    /// let mut counter = Counter::default();
+    /// model.write(&mut counter, &args).unwrap();
+    /// assert_eq!(counter.flushes, 0);
+    /// ```
+    /// 
+    /// # Errors
+    /// 
+    /// * `GenerateError::MissingArgument` - An argument referenced by the model was not given.
+    /// * `GenerateError::DepthExceeded` - The nested models are deeper than `Model::DEFAULT_MAX_DEPTH`.
+    /// * `GenerateError::Io` - An IO error occurred while writing or flushing.
+    #[cfg(feature = "std")]
+    pub fn write_buffered<W: io::Write>(&self, writer: &mut W, args: &[Argument], flush_every: usize) -> Result<(), GenerateError> {
+        let mut writer = FlushingWriter { writer, flush_every, unflushed: 0 };
+        self.generate_to(&mut writer, args)?;
+        io::Write::flush(&mut writer)?;
+        Ok(())
+    }
+
+    /// Returns a reader producing the generated image, part by part.
+    /// 
+    /// Nothing is generated in advance: the texts of the model are read in place,
+    /// and each other part is generated when it is reached. A generation error
+    /// is returned by the read reaching the part, as an `io::Error` (the IO errors
+    /// are returned as is, the others are wrapped in an `io::ErrorKind::Other` error).
+    /// 
+    /// Requires the `std` feature.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `args` - The arguments to use.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use std::io::Read;
+    /// use svggen::{Model, Argument};
+    /// 
+    /// let model: Model = "<svg>\n#FOR 1\n<text>#GET 0 #ITEM</text>\n#END\n</svg>".parse().unwrap();
+    /// let args = [
+    ///     Argument::from("Item".as_bytes()),
+    ///     Argument::List(vec![Argument::number(1.0), Argument::number(2.0)]),
+    /// ];
+    /// 
+    /// // Read the image in small chunks
+    /// let mut reader = model.reader(&args);
+    /// let mut content = Vec::new();
+    /// let mut chunk = [0; 3];
+    /// loop {
+    ///     let len = reader.read(&mut chunk).unwrap();
+    ///     if len == 0 {
+    ///         break;
+    ///     }
+    ///     content.extend_from_slice(&chunk[..len]);
+    /// }
+    /// assert_eq!(content, model.generate(&args).unwrap().content());
+    /// 
+    /// // The generation errors are returned when they are reached
+    /// let model: Model = "<svg>#GET 0</svg>".parse().unwrap();
+    /// let mut content = Vec::new();
+    /// let error = model.reader(&[]).read_to_end(&mut content).unwrap_err();
+    /// assert_eq!(error.to_string(), "Missing argument: 0");
+    /// assert_eq!(content, b"<svg>");
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn reader<'a>(&'a self, args: &'a [Argument<'a>]) -> impl io::Read + 'a {
+        ModelReader { parts: &self.parts, args, variables: self.cached_variables(), text: &[], buffer: Vec::new(), position: 0 }
+    }
+
+    /// Write the model to a writer using named arguments.
+    /// 
+    /// Requires the `std` feature.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `writer` - The writer to write the model to.
+    /// * `args` - The named arguments to use.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use std::collections::HashMap;
+    /// use svggen::{Model, ModelPart, Argument};
+    /// 
+    /// let model = Model::from(vec![
+    ///     ModelPart::from("Hello ".as_bytes()),
+    ///     ModelPart::NamedArgument("name".into()),
+    ///     ModelPart::from("!".as_bytes()),
+    /// ]);
+    /// 
+    /// let args = HashMap::from([("name", Argument::from("World".as_bytes()))]);
+    /// 
+    /// let mut buffer: Vec<u8> = Vec::new();
+    /// model.write_named(&mut buffer, &args).unwrap();
+    /// 
+    /// assert_eq!(buffer, b"Hello World!");
+    /// ```
+    /// 
+    /// # Errors
+    /// 
+    /// * `GenerateError::MissingArgument` - A positional argument is referenced by the model.
+    /// * `GenerateError::MissingNamedArgument` - A named argument referenced by the model was not given.
+    /// * `GenerateError::Io` - An IO error occurred while writing.
+    /// 
+    /// ```
+    /// use std::collections::HashMap;
+    /// use svggen::{Model, GenerateError};
+    /// 
+    /// let model: Model = "<text>#GET title</text>".parse().unwrap();
+    /// let mut buffer: Vec<u8> = Vec::new();
+    /// 
+    /// let error = model.write_named(&mut buffer, &HashMap::new()).unwrap_err();
+    /// assert!(matches!(&error, GenerateError::MissingNamedArgument { name } if &**name == "title"));
+    /// assert_eq!(error.name(), Some("title"));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn write_named<W: io::Write>(&self, writer: &mut W, args: &HashMap<&str, Argument>) -> Result<(), GenerateError> {
+        write_parts(&self.parts, writer, Context::new(&[], args, self.cached_variables(), Self::DEFAULT_MAX_DEPTH))
+    }
+
+    /// Returns the exact size of the image that would be generated with the given arguments.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `args` - The arguments to use.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, Argument};
+    /// 
+    /// let model: Model = "Hello #GET 0!".parse().unwrap();
+    /// let args = [Argument::from("World".as_bytes())];
+    /// 
+    /// assert_eq!(model.output_size(&args), Some(12));
+    /// assert_eq!(model.output_size(&args), Some(model.generate(&args).unwrap().content().len()));
+    /// 
+    /// // The size cannot be known if an argument is missing
+    /// assert_eq!(model.output_size(&[]), None);
+    /// ```
+    pub fn output_size(&self, args: &[Argument]) -> Option<usize> {
+        output_size_parts(&self.parts, Context::new(args, &Map::new(), self.cached_variables(), Self::DEFAULT_MAX_DEPTH))
+    }
+
+    /// Creates an image from the model.
+    /// 
+    /// The arguments are usually a slice, generated as with `Model::generate_with_limit`
+    /// at `Model::DEFAULT_MAX_DEPTH` into a buffer preallocated with `Model::output_size`.
+    /// Any other `ArgumentSource` can give them, resolving them only when needed: as
+    /// their size is not known beforehand, the buffer is preallocated with
+    /// `Model::static_len` instead.
+    /// 
+    /// The bytes of each argument are written exactly where it is referenced: no
+    /// whitespace or line terminator is added or removed around it, whether the
+    /// reference is on its own line or inside an attribute.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `args` - The arguments to use.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, ModelPart, Image, Argument};
+    /// 
+    /// let model = Model::from(vec![
+    ///     ModelPart::from("Hello ".as_bytes()),
+    ///     ModelPart::Argument(0),
+    ///     ModelPart::from("!".as_bytes()),
+    /// ]);
+    /// 
+    /// let image = Image::from("World".as_bytes());
+    /// let args = [Argument::Image(&image)];
+    /// 
+    /// let image = model.generate(&args).unwrap();
+    /// 
+    /// assert_eq!(image.content(), b"Hello World!");
+    /// ```
+    /// 
+    /// The arguments are substituted byte for byte:
+    /// 
+    /// ```
+    /// use svggen::{Model, Argument};
+    /// 
+    /// let model: Model = "<rect fill=\"#GET 0\"/>\n#GET 1\n<text>#GET 0#GET 1</text>".parse().unwrap();
+    /// let args = [Argument::from("red".as_bytes()), Argument::from(b"\r\n \t\xff\n".as_slice())];
+    /// 
+    /// let image = model.generate(&args).unwrap();
+    /// assert_eq!(image.content(), b"<rect fill=\"red\"/>\n\r\n \t\xff\n\n<text>red\r\n \t\xff\n</text>");
+    /// 
+    /// // An empty argument leaves the text around it unchanged
+    /// let image = model.generate(&[Argument::Empty, Argument::Empty]).unwrap();
+    /// assert_eq!(image.content(), b"<rect fill=\"\"/>\n\n<text></text>");
+    /// ```
+    /// 
+    /// A model can be given as an argument, with its own arguments:
+    /// 
+    /// ```
+    /// use svggen::{Model, Argument};
+    /// 
+    /// let card: Model = "<g>#GET 0</g>".parse().unwrap();
+    /// let badge: Model = "<text>#GET 0</text>".parse().unwrap();
+    /// 
+    /// let args = [Argument::Model {
+    ///     model: &badge,
+    ///     args: vec![Argument::from("New".as_bytes())],
+    /// }];
+    /// 
+    /// let image = card.generate(&args).unwrap();
+    /// assert_eq!(image.content(), b"<g><text>New</text></g>");
+    /// ```
+    /// 
+    /// # Errors
+    /// 
+    /// * `GenerateError::MissingArgument` - An argument referenced by the model was not given.
+    /// * `GenerateError::DepthExceeded` - The nested models are deeper than `Model::DEFAULT_MAX_DEPTH`.
+    /// 
+    /// ```
+    /// use svggen::{Model, ModelPart};
+    /// 
+    /// let model = Model::from(vec![ModelPart::Argument(1)]);
+    /// 
+    /// let error = model.generate(&[]).unwrap_err();
+    /// assert_eq!(error.index(), Some(1));
+    /// ```
+    pub fn generate<S: ArgumentSource + ?Sized>(&self, args: &S) -> Result<Image, GenerateError> {
+        if let Some(args) = args.as_slice() {
+            return self.generate_with_limit(args, Self::DEFAULT_MAX_DEPTH);
+        }
+
+        // The size of the arguments is unknown until they are resolved
+        let mut buffer = Vec::with_capacity(self.static_len());
+        self.write_resolved(&mut buffer, args)?;
+        Ok(Image { content: buffer.into() })
+    }
+
+    /// Creates an image from the model, with a maximum depth of nested models.
+    /// 
+    /// `Model::generate` uses `Model::DEFAULT_MAX_DEPTH` as the maximum depth.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `args` - The arguments to use.
+    /// * `max_depth` - The maximum number of nested models that can be expanded inside each other.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, Argument, GenerateError};
+    /// 
+    /// let model: Model = "<g>#GET 0</g>".parse().unwrap();
+    /// 
+    /// // Nest the model inside itself 100 times
+    /// let mut arg = Argument::from("Hello".as_bytes());
+    /// for _ in 0..100 {
+    ///     arg = Argument::Model { model: &model, args: vec![arg] };
+    /// }
+    /// let args = [arg];
+    /// 
+    /// assert!(model.generate_with_limit(&args, 100).is_ok());
+    /// 
+    /// let error = model.generate_with_limit(&args, 10).unwrap_err();
+    /// assert!(matches!(error, GenerateError::DepthExceeded { max_depth: 10 }));
+    /// 
+    /// let error = model.generate(&args).unwrap_err();
+    /// assert!(matches!(error, GenerateError::DepthExceeded { max_depth: Model::DEFAULT_MAX_DEPTH }));
+    /// ```
+    /// 
+    /// # Errors
+    /// 
+    /// * `GenerateError::MissingArgument` - An argument referenced by the model was not given.
+    /// * `GenerateError::DepthExceeded` - The nested models are deeper than `max_depth`.
+    pub fn generate_with_limit(&self, args: &[Argument], max_depth: usize) -> Result<Image, GenerateError> {
+        let named = Map::new();
+        let context = Context::new(args, &named, self.cached_variables(), max_depth);
+        let mut buffer = Vec::with_capacity(output_size_parts(&self.parts, context).unwrap_or(0));
+        write_parts(&self.parts, &mut buffer, context)?;
+        Ok(Image { content: buffer.into() })
+    }
+
+    /// Creates an image from the model for each set of arguments.
+    /// 
+    /// Each image is generated into the same scratch buffer, cleared before each set
+    /// of arguments, and then copied into an image of the exact size. Rendering many
+    /// sets of arguments (such as the rows of a table) therefore allocates the buffer
+    /// once instead of once per image as `Model::generate` does.
+    /// 
+    /// Returns the result of each set of arguments, in the same order: an error for a
+    /// set does not stop the other sets.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `arg_sets` - The arguments of each image.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, Argument, GenerateError};
+    /// 
+    /// let model: Model = "<text>#GET 0</text>".parse().unwrap();
+    /// let arg_sets = vec![
+    ///     vec![Argument::from("Alice".as_bytes())],
+    ///     vec![],
+    ///     vec![Argument::from("Bob".as_bytes())],
+    /// ];
+    /// 
+    /// let images = model.render_all(&arg_sets);
+    /// assert_eq!(images.len(), 3);
+    /// assert_eq!(images[0].as_ref().unwrap().content(), b"<text>Alice</text>");
+    /// assert!(matches!(images[1], Err(GenerateError::MissingArgument { index: 0 })));
+    /// assert_eq!(images[2].as_ref().unwrap().content(), b"<text>Bob</text>");
+    /// ```
+    pub fn render_all(&self, arg_sets: &[Vec<Argument>]) -> Vec<Result<Image, GenerateError>> {
+        let mut buffer: Vec<u8> = Vec::new();
+        arg_sets.iter().map(|args| {
+            self.generate_into(args, &mut buffer)?;
+            Ok(Image { content: buffer.as_slice().into() })
+        }).collect()
+    }
+
+    /// Generates the image into the given buffer, reusing its allocation.
+    /// 
+    /// The buffer is cleared first, so it only contains the generated image. If
+    /// an error is returned, it contains the part generated before the error.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `args` - The arguments to use.
+    /// * `buffer` - The buffer to generate the image into.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, Argument};
+    /// 
+    /// let model: Model = "<text>#GET 0</text>".parse().unwrap();
+    /// let mut buffer = Vec::new();
+    /// 
+    /// model.generate_into(&[Argument::from("A longer first text")], &mut buffer).unwrap();
+    /// assert_eq!(buffer, b"<text>A longer first text</text>");
+    /// 
+    /// model.generate_into(&[Argument::from("Short")], &mut buffer).unwrap();
+    /// assert_eq!(buffer, b"<text>Short</text>");
+    /// assert_eq!(buffer, model.generate(&[Argument::from("Short")]).unwrap().content());
+    /// ```
+    /// 
+    /// # Errors
+    /// 
+    /// * `GenerateError::MissingArgument` - An argument referenced by the model was not given.
+    /// * `GenerateError::DepthExceeded` - The nested models are deeper than `Model::DEFAULT_MAX_DEPTH`.
+    pub fn generate_into(&self, args: &[Argument], buffer: &mut Vec<u8>) -> Result<(), GenerateError> {
+        buffer.clear();
+        write_parts(&self.parts, buffer, Context::new(args, &Map::new(), self.cached_variables(), Self::DEFAULT_MAX_DEPTH))
+    }
+
+    /// Write the model to a writer, writing missing arguments as `Argument::Empty`.
+    /// 
+    /// `Model::write` returns an error when an argument is missing (out of the given
+    /// arguments), while an `Argument::Empty` writes nothing. With this method, a
+    /// missing argument is not an error and writes nothing as well, which is useful
+    /// for optional trailing arguments. Missing named arguments are also written as
+    /// `Argument::Empty`, including in nested models.
+    /// 
+    /// Requires the `std` feature.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `writer` - The writer to write the model to.
+    /// * `args` - The arguments to use.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, Argument};
+    /// 
+    /// let model: Model = "<text>#GET 0#GET 1</text>".parse().unwrap();
+    /// let args = [Argument::from("Hello".as_bytes())];
+    /// 
+    /// // The strict method fails because the argument 1 is missing
+    /// let mut buffer: Vec<u8> = Vec::new();
+    /// assert!(model.write(&mut buffer, &args).is_err());
+    /// 
+    /// let mut buffer: Vec<u8> = Vec::new();
+    /// model.write_lenient(&mut buffer, &args).unwrap();
+    /// assert_eq!(buffer, b"<text>Hello</text>");
+    /// ```
+    /// 
+    /// # Errors
+    /// 
+    /// * `GenerateError::DepthExceeded` - The nested models are deeper than `Model::DEFAULT_MAX_DEPTH`.
+    /// * `GenerateError::Io` - An IO error occurred while writing.
+    #[cfg(feature = "std")]
+    pub fn write_lenient<W: io::Write>(&self, writer: &mut W, args: &[Argument]) -> Result<(), GenerateError> {
+        write_parts(&self.parts, writer, Context::new(args, &Map::new(), self.cached_variables(), Self::DEFAULT_MAX_DEPTH).lenient())
+    }
+
+    /// Creates an image from the model, writing missing arguments as `Argument::Empty`.
+    /// 
+    /// See `Model::write_lenient` for the difference with `Model::generate`.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `args` - The arguments to use.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, Argument, GenerateError};
+    /// 
+    /// let model: Model = "<text>#GET 0</text><text>#GET 5</text>".parse().unwrap();
+    /// let args = [Argument::from("Hello".as_bytes())];
+    /// 
+    /// let error = model.generate(&args).unwrap_err();
+    /// assert!(matches!(error, GenerateError::MissingArgument { index: 5 }));
+    /// 
+    /// let image = model.generate_lenient(&args).unwrap();
+    /// assert_eq!(image.content(), b"<text>Hello</text><text></text>");
+    /// assert_eq!(image, model.generate(&[args[0].clone(), Argument::Empty, Argument::Empty, Argument::Empty, Argument::Empty, Argument::Empty]).unwrap());
+    /// ```
+    /// 
+    /// # Errors
+    /// 
+    /// * `GenerateError::DepthExceeded` - The nested models are deeper than `Model::DEFAULT_MAX_DEPTH`.
+    pub fn generate_lenient(&self, args: &[Argument]) -> Result<Image, GenerateError> {
+        let named = Map::new();
+        let context = Context::new(args, &named, self.cached_variables(), Self::DEFAULT_MAX_DEPTH).lenient();
+        let mut buffer = Vec::with_capacity(output_size_parts(&self.parts, context).unwrap_or(0));
+        write_parts(&self.parts, &mut buffer, context)?;
+        Ok(Image { content: buffer.into() })
+    }
+
+    /// Creates an image from the model using named arguments.
+    /// 
+    /// Requires the `std` feature.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `args` - The named arguments to use.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use std::collections::HashMap;
+    /// use rutil::read::Readable;
+    /// use svggen::{Model, Argument};
+    /// 
+    /// let mut data = "<text>#GET title</text>".as_bytes();
+    /// let model = Model::load(&mut data).unwrap();
+    /// 
+    /// let args = HashMap::from([("title", Argument::from("Hello".as_bytes()))]);
+    /// let image = model.generate_named(&args).unwrap();
+    /// 
+    /// assert_eq!(image.content(), b"<text>Hello</text>");
+    /// ```
+    /// 
+    /// # Errors
+    /// 
+    /// * `GenerateError::MissingArgument` - A positional argument is referenced by the model.
+    /// * `GenerateError::MissingNamedArgument` - A named argument referenced by the model was not given.
+    /// 
+    /// ```
+    /// use std::collections::HashMap;
+    /// use svggen::{Model, ModelPart};
+    /// 
+    /// let model = Model::from(vec![ModelPart::NamedArgument("title".into())]);
+    /// 
+    /// let error = model.generate_named(&HashMap::new()).unwrap_err();
+    /// assert_eq!(error.name(), Some("title"));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn generate_named(&self, args: &HashMap<&str, Argument>) -> Result<Image, GenerateError> {
+        let size = output_size_parts(&self.parts, Context::new(&[], args, self.cached_variables(), Self::DEFAULT_MAX_DEPTH));
+        let mut buffer = Vec::with_capacity(size.unwrap_or(0));
+        self.write_named(&mut buffer, args)?;
+        Ok(Image { content: buffer.into() })
+    }
+
+    /// Write the model to a writer, using positional and named arguments.
+    /// 
+    /// Requires the `std` feature.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `writer` - The writer to write the model to.
+    /// * `args` - The arguments to use.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, Argument, Arguments};
+    /// 
+    /// let model: Model = "<text>#GET title: #GET 0</text>".parse().unwrap();
+    /// let args = Arguments::from(vec![Argument::from("1".as_bytes())])
+    ///     .with_named("title", Argument::from("Page".as_bytes()));
+    /// 
+    /// let mut buffer: Vec<u8> = Vec::new();
+    /// model.write_with(&mut buffer, &args).unwrap();
+    /// 
+    /// assert_eq!(buffer, b"<text>Page: 1</text>");
+    /// ```
+    /// 
+    /// # Errors
+    /// 
+    /// * `GenerateError::MissingArgument` - A positional argument referenced by the model was not given.
+    /// * `GenerateError::MissingNamedArgument` - A named argument referenced by the model was not given.
+    /// * `GenerateError::DepthExceeded` - The nested models are deeper than `Model::DEFAULT_MAX_DEPTH`.
+    /// * `GenerateError::Io` - An IO error occurred while writing.
+    #[cfg(feature = "std")]
+    pub fn write_with<W: io::Write>(&self, writer: &mut W, args: &Arguments) -> Result<(), GenerateError> {
+        write_parts(&self.parts, writer, Context::new(&args.positional, &args.named, self.cached_variables(), Self::DEFAULT_MAX_DEPTH))
+    }
+
+    /// Write the model to a writer, resolving the arguments from a source one by one.
+    fn write_resolved<W: Output + ?Sized, S: ArgumentSource + ?Sized>(&self, writer: &mut W, source: &S) -> Result<(), GenerateError> {
+        let resolve = |index| source.resolve(index);
+        let resolved = ResolvedArguments::new(&resolve, self.cached_arguments());
+        let named = Map::new();
+        let context = Context::new(&[], &named, self.cached_variables(), Self::DEFAULT_MAX_DEPTH).resolved(&resolved);
+        write_parts(&self.parts, writer, context)
+    }
+
+    /// Creates an image from the model, using positional and named arguments.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `args` - The arguments to use.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, Argument, Arguments, GenerateError};
+    /// 
+    /// let model: Model = "<text>#GET title: #GET 0</text>".parse().unwrap();
+    /// 
+    /// let args = Arguments::new()
+    ///     .with(Argument::from("1".as_bytes()))
+    ///     .with_named("title", Argument::from("Page".as_bytes()));
+    /// assert_eq!(model.generate_with(&args).unwrap().content(), b"<text>Page: 1</text>");
+    /// 
+    /// // A named argument is never used for a positional reference
+    /// let args = Arguments::new().with_named("title", Argument::from("Page".as_bytes()));
+    /// let error = model.generate_with(&args).unwrap_err();
+    /// assert!(matches!(error, GenerateError::MissingArgument { index: 0 }));
+    /// ```
+    /// 
+    /// # Errors
+    /// 
+    /// * `GenerateError::MissingArgument` - A positional argument referenced by the model was not given.
+    /// * `GenerateError::MissingNamedArgument` - A named argument referenced by the model was not given.
+    /// * `GenerateError::DepthExceeded` - The nested models are deeper than `Model::DEFAULT_MAX_DEPTH`.
+    pub fn generate_with(&self, args: &Arguments) -> Result<Image, GenerateError> {
+        let context = Context::new(&args.positional, &args.named, self.cached_variables(), Self::DEFAULT_MAX_DEPTH);
+        let mut buffer = Vec::with_capacity(output_size_parts(&self.parts, context).unwrap_or(0));
+        write_parts(&self.parts, &mut buffer, context)?;
+        Ok(Image { content: buffer.into() })
+    }
+
+    /// Creates an image from the model, as a string.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `args` - The arguments to use.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, Argument};
+    /// 
+    /// let model: Model = "<text>#GET 0</text>".parse().unwrap();
+    /// 
+    /// let svg: String = model.render_to_string(&[Argument::from("Hello".as_bytes())]).unwrap();
+    /// assert_eq!(svg, "<text>Hello</text>");
+    /// ```
+    /// 
+    /// # Errors
+    /// 
+    /// * `GenerateError::MissingArgument` - An argument referenced by the model was not given.
+    /// * `GenerateError::DepthExceeded` - The nested models are deeper than `Model::DEFAULT_MAX_DEPTH`.
+    /// * `GenerateError::Utf8` - The generated image is not valid UTF-8.
+    /// 
+    /// ```
+    /// use svggen::{Model, Argument, GenerateError};
+    /// 
+    /// let model: Model = "<text>#GET 0</text>".parse().unwrap();
+    /// 
+    /// let error = model.render_to_string(&[Argument::Text(vec![0xff].into())]).unwrap_err();
+    /// assert!(matches!(error, GenerateError::Utf8(_)));
+    /// ```
+    pub fn render_to_string(&self, args: &[Argument]) -> Result<String, GenerateError> {
+        let image = self.generate(args)?;
+        String::from_utf8(image.content.into_vec()).map_err(|err| GenerateError::Utf8(err.utf8_error()))
+    }
+
+    /// Write the model to a `fmt::Write`, such as a `String` or a `fmt::Formatter`.
+    /// 
+    /// The image is generated before being written, so nothing is written if an error occurs.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `writer` - The writer to write the model to.
+    /// * `args` - The arguments to use.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, Argument};
+    /// 
+    /// let model: Model = "<text>#GET 0</text>".parse().unwrap();
+    /// 
+    /// let mut svg = String::from("<svg>");
+    /// model.render_to_fmt(&mut svg, &[Argument::from("Hello".as_bytes())]).unwrap();
+    /// svg.push_str("</svg>");
+    /// 
+    /// assert_eq!(svg, "<svg><text>Hello</text></svg>");
+    /// ```
+    /// 
+    /// # Errors
+    /// 
+    /// * `GenerateError::MissingArgument` - An argument referenced by the model was not given.
+    /// * `GenerateError::DepthExceeded` - The nested models are deeper than `Model::DEFAULT_MAX_DEPTH`.
+    /// * `GenerateError::Utf8` - The generated image is not valid UTF-8.
+    /// * `GenerateError::Fmt` - The writer returned an error.
+    pub fn render_to_fmt<W: fmt::Write>(&self, writer: &mut W, args: &[Argument]) -> Result<(), GenerateError> {
+        writer.write_str(&self.render_to_string(args)?).map_err(GenerateError::Fmt)
+    }
+
+    /// Creates a new model from an asynchronous reader, as `Model::load` does.
+    /// 
+    /// The content is read asynchronously, then parsed by the same parser as `Model::load`.
+    /// 
+    /// Requires the `tokio` feature.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `reader` - The reader to read the model from.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use rutil::read::Readable;
+    /// use svggen::Model;
+    /// 
+    /// let source = "<svg>\n#IF 0\n<text>#GET 0</text>\n#END\n</svg>";
+    /// 
+    /// let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    /// let model = runtime.block_on(async {
+    ///     let mut reader = tokio::io::BufReader::new(source.as_bytes());
+    ///     Model::load_async(&mut reader).await
+    /// }).unwrap();
+    /// 
+    /// assert_eq!(model, Model::load(&mut source.as_bytes()).unwrap());
+    /// ```
+    /// 
+    /// # Errors
+    /// 
+    /// * `ReadError::Parse(ParseError)` - The model is invalid (see `ParseErrorKind`).
+    /// * `ReadError::Io` - An IO error occurred while reading.
+    #[cfg(feature = "tokio")]
+    pub async fn load_async<R: tokio::io::AsyncRead + Unpin>(reader: &mut R) -> Result<Self, ReadError<ParseError>> {
+        use tokio::io::AsyncReadExt;
+        let mut content = Vec::new();
+        reader.read_to_end(&mut content).await?;
+        ModelParser::new().parse(&mut content.as_slice())
+    }
+
+    /// Loads a model from a memory-mapped file.
+    /// 
+    /// The texts of the model are not copied: the parser records where each text
+    /// is in the file, and the `MappedModel` reads them from the mapped file when
+    /// generating an image. Only the other parts (the references, the directives...)
+    /// are allocated, which keeps the memory used by large collections of models
+    /// loaded at startup close to the size of the references. The file is parsed
+    /// as `Model::load` does, a text being split where an escaping prefix or a
+    /// directive line is removed.
+    /// 
+    /// Requires the `memmap2` feature.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `path` - The path of the file to map.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use rutil::read::Readable;
+    /// use svggen::{Model, Argument};
+    /// 
+    /// let source = "##GET 0 is escaped\n#REM A comment\n<svg>\n#DEFAULT 1 Greeting\n#IF 1\n<title>#GET 1</title>\n#END\n<text>#GET 0</text>\n</svg>";
+    /// let path = std::env::temp_dir().join("svggen_load_mmap.svg");
+    /// std::fs::write(&path, source).unwrap();
+    /// 
+    /// // SAFETY: the file is not modified while it is mapped
+    /// let mapped = unsafe { Model::load_mmap(&path) }.unwrap();
+    /// let model = Model::load(&mut source.as_bytes()).unwrap();
+    /// 
+    /// let args = [Argument::from("Hello"), Argument::from("Hi")];
+    /// assert_eq!(mapped.generate(&args).unwrap(), model.generate(&args).unwrap());
+    /// assert_eq!(mapped.to_model(), model);
+    /// 
+    /// // Every text is read from the file, including inside the blocks
+    /// assert_eq!(mapped.mapped_len(), model.static_len());
+    /// 
+    /// drop(mapped);
+    /// std::fs::remove_file(&path).unwrap();
+    /// ```
+    /// 
+    /// # Errors
+    /// 
+    /// * `ReadError::Parse(ParseError)` - The model is invalid (see `ParseErrorKind`).
+    /// * `ReadError::Io` - An IO error occurred while opening or mapping the file.
+    /// 
+    /// # Safety
+    /// 
+    /// The file must not be modified or truncated, by this process or another one,
+    /// while the returned `MappedModel` exists: the texts would change under it,
+    /// and reading a truncated mapping is undefined behavior (see `memmap2::Mmap::map`).
+    #[cfg(feature = "memmap2")]
+    pub unsafe fn load_mmap<P: AsRef<Path>>(path: P) -> Result<MappedModel, ReadError<ParseError>> {
+        let file = fs::File::open(path)?;
+        // SAFETY: the caller guarantees that the file is not modified while mapped
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+        let parts = ModelParser::new().parse_parts::<MappedPart, io::Error>(&map, None)?;
+
+        let mut variables = Map::new();
+        mapped_variables(&parts, &mut variables);
+        Ok(MappedModel { map, parts, variables })
+    }
+
+    /// Creates a new model from a reader, as `Model::load` does, expanding the includes.
+    /// 
+    /// A line containing only `#INCLUDE name` is replaced by the content given by
+    /// `resolver` for `name` (the rest of the line, without the surrounding whitespace).
+    /// The content is inserted as is and parsed with the rest of the model, so it
+    /// can use the arguments, the variables and the `#ITEM` of the model, and it can
+    /// include other contents. It should end with a line terminator, otherwise its
+    /// last line is joined with the line after the `#INCLUDE`. The resolver decides
+    /// where the contents come from (files, embedded assets...), `Model::load` does
+    /// not expand the includes and keeps the `#INCLUDE` lines as text.
+    /// 
+    /// Requires the `std` feature.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `reader` - The reader to read the model from.
+    /// * `resolver` - The function giving the content included for each name.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use std::collections::HashMap;
+    /// use std::io;
+    /// use svggen::{Model, Argument};
+    /// 
+    /// let assets = HashMap::from([
+    ///     ("defs", "<defs>\n#INCLUDE gradient\n</defs>\n"),
+    ///     ("gradient", "<linearGradient id=\"#GET 0\"/>\n"),
+    /// ]);
+    /// let resolver = |name: &str| match assets.get(name) {
+    ///     Some(content) => Ok(content.as_bytes().to_vec()),
+    ///     None => Err(io::Error::new(io::ErrorKind::NotFound, name)),
+    /// };
+    /// 
+    /// let source = "<svg>\n#INCLUDE defs\n<rect fill=\"url(##GET 0)\"/>\n</svg>";
+    /// let model = Model::load_with_resolver(&mut source.as_bytes(), resolver).unwrap();
+    /// 
+    /// let image = model.generate(&[Argument::from("sky".as_bytes())]).unwrap();
+    /// assert_eq!(
+    ///     image.content(),
+    ///     b"<svg>\n<defs>\n<linearGradient id=\"sky\"/>\n</defs>\n<rect fill=\"url(#GET 0)\"/>\n</svg>",
+    /// );
+    /// 
+    /// // The resolver errors are returned
+    /// let source = "<svg>\n#INCLUDE missing\n</svg>";
+    /// assert!(Model::load_with_resolver(&mut source.as_bytes(), resolver).is_err());
+    /// ```
+    /// 
+    /// A content cannot include itself, even through other contents:
+    /// 
+    /// ```
+    /// use std::io;
+    /// use svggen::{Model, ParseErrorKind};
+    /// use rutil::read::ReadError;
+    /// 
+    /// let resolver = |name: &str| Ok(match name {
+    ///     "a" => b"<a>\n#INCLUDE b\n</a>\n".to_vec(),
+    ///     _ => b"<b>\n#INCLUDE a\n</b>\n".to_vec(),
+    /// });
+    /// 
+    /// let source = "<svg>\n#INCLUDE a\n</svg>";
+    /// match Model::load_with_resolver(&mut source.as_bytes(), resolver) {
+    ///     Err(ReadError::Parse(error)) => {
+    ///         assert_eq!((error.line(), error.kind()), (2, ParseErrorKind::CyclicInclude));
+    ///     }
+    ///     _ => panic!("the cyclic include is not detected"),
+    /// }
+    /// 
+    /// // The same content can still be included several times
+    /// let resolver = |_: &str| Ok(b"<g/>\n".to_vec());
+    /// let source = "#INCLUDE g\n#INCLUDE g\n";
+    /// let model = Model::load_with_resolver(&mut source.as_bytes(), resolver).unwrap();
+    /// assert_eq!(model.to_string(), "<g/>\n<g/>\n");
+    /// ```
+    /// 
+    /// # Errors
+    /// 
+    /// * `ReadError::Parse(ParseError)` - The model is invalid (see `ParseErrorKind`), or a
+    ///   content includes itself (`ParseErrorKind::CyclicInclude`).
+    /// * `ReadError::Io` - An IO error occurred while reading, or returned by the resolver.
+    #[cfg(feature = "std")]
+    pub fn load_with_resolver<R, F>(reader: &mut R, resolver: F) -> Result<Self, ReadError<ParseError>>
+    where
+        R: io::Read,
+        F: Fn(&str) -> io::Result<Vec<u8>>,
+    {
+        ModelParser::new().parse_with_resolver(reader, resolver)
+    }
+
+    /// Loads the models of a folder and its subfolders into a `TemplateSet`.
+    /// 
+    /// The name of each model is the path of its file relative to the folder, as
+    /// with `FolderLoad::load_folder_recursive` (for example `cards/hero.svg`).
+    /// The files that cannot be loaded are ignored.
+    /// 
+    /// Requires the `std` feature.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `folder` - The path to the folder to read from.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use std::fs;
+    /// use svggen::{Model, Argument};
+    /// 
+    /// let folder = std::env::temp_dir().join("svggen_from_template_dir");
+    /// # let _ = fs::remove_dir_all(&folder);
+    /// fs::create_dir_all(folder.join("cards")).unwrap();
+    /// fs::write(folder.join("base.svg"), "<svg>#GET 0</svg>").unwrap();
+    /// fs::write(folder.join("cards/hero.svg"), "<g>#GET 0</g>").unwrap();
+    /// 
+    /// let templates = Model::from_template_dir(&folder);
+    /// assert_eq!(templates.names(), ["base.svg", "cards/hero.svg"]);
+    /// 
+    /// let image = templates.render("cards/hero.svg", &[Argument::from("Hero".as_bytes())]).unwrap();
+    /// assert_eq!(image.content(), b"<g>Hero</g>");
+    /// assert!(templates.render("cards/villain.svg", &[]).is_err());
+    /// # fs::remove_dir_all(&folder).unwrap();
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn from_template_dir<P: AsRef<Path>>(folder: P) -> TemplateSet {
+        TemplateSet::from(Model::load_folder_recursive(folder))
+    }
+
+    /// Creates multiple models from a reader, separated by a separator line.
+    /// 
+    /// A separator line is a line whose content, without the surrounding whitespace,
+    /// is `separator` (for example `---`). The separator lines are removed, and each
+    /// chunk between them is parsed as its own model, keeping the line terminator of
+    /// its last line. The chunks that only contain whitespace are skipped.
+    /// 
+    /// Requires the `std` feature.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `reader` - The reader to read the models from.
+    /// * `separator` - The content of the separator lines.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::Model;
+    /// 
+    /// let mut data = "<svg>#GET 0</svg>\n---\n<g/>\n---\n\n---\n<text>#GET title</text>".as_bytes();
+    /// let models = Model::load_many(&mut data, "---").unwrap();
+    /// 
+    /// assert_eq!(models, [
+    ///     "<svg>#GET 0</svg>\n".parse::<Model>().unwrap(),
+    ///     "<g/>\n".parse::<Model>().unwrap(),
+    ///     "<text>#GET title</text>".parse::<Model>().unwrap(),
+    /// ]);
+    /// ```
+    /// 
+    /// # Errors
+    /// 
+    /// * `ReadError::Parse(ParseError)` - A model is invalid (see `ParseErrorKind`).
+    /// * `ReadError::Io` - An IO error occurred while reading.
+    #[cfg(feature = "std")]
+    pub fn load_many<R: io::Read>(reader: &mut R, separator: &str) -> Result<Vec<Model>, ReadError<ParseError>> {
+        let parser = ModelParser::new();
+        let mut models = Vec::new();
+        let mut chunk: Vec<u8> = Vec::new();
+
+        // For each line (keeping its original line terminator)
+        let mut reader = io::BufReader::new(reader);
+        loop {
+            let start = chunk.len();
+            let read = reader.read_until(b'\n', &mut chunk)?;
+            let end = read == 0;
+            if !end && chunk[start..].trim_ascii() != separator.as_bytes() {
+                continue;
+            }
+
+            // Parse the chunk before the separator line
+            chunk.truncate(start);
+            if !chunk.trim_ascii().is_empty() {
+                models.push(parser.parse(&mut chunk.as_slice())?);
+            }
+            chunk.clear();
+            if end {
+                return Ok(models);
+            }
+        }
+    }
+}
+
+/// The arguments resolved from an `ArgumentSource` during a generation.
+struct ResolvedArguments<'s> {
+    /// The function resolving an argument.
+    resolve: &'s dyn Fn(usize) -> Option<Argument<'s>>,
+
+    /// The indices used by the model (sorted) with their argument, resolved when first needed.
+    cells: Box<[(usize, OnceCell<Option<Argument<'s>>>)]>,
+}
+
+impl<'s> ResolvedArguments<'s> {
+    /// Creates the arguments of a model using the given indices, without resolving them.
+    fn new(resolve: &'s dyn Fn(usize) -> Option<Argument<'s>>, indices: &BTreeSet<usize>) -> Self {
+        ResolvedArguments { resolve, cells: indices.iter().map(|index| (*index, OnceCell::new())).collect() }
+    }
+}
+
+/// The arguments looked up by index during a generation, whatever their origin.
+trait ArgumentLookup {
+    /// Returns the argument of the given index, resolving it if needed.
+    fn get(&self, index: usize) -> Option<&Argument<'_>>;
+}
+
+impl ArgumentLookup for ResolvedArguments<'_> {
+    /// Returns the argument, resolving it from the source the first time.
+    fn get(&self, index: usize) -> Option<&Argument<'_>> {
+        let position = self.cells.binary_search_by_key(&index, |(index, _)| *index).ok()?;
+        self.cells[position].1.get_or_init(|| (self.resolve)(index)).as_ref()
+    }
+}
+
+/// The positional arguments of a generation.
+#[derive(Clone, Copy)]
+enum Args<'c> {
+    /// The arguments are given as a slice.
+    Slice(&'c [Argument<'c>]),
+
+    /// The arguments are resolved from an `ArgumentSource`.
+    Resolved(&'c dyn ArgumentLookup),
+}
+
+impl<'c> Args<'c> {
+    /// Returns the argument of the given index, resolving it if needed.
+    fn get(self, index: usize) -> Option<&'c Argument<'c>> {
+        match self {
+            Args::Slice(args) => args.get(index),
+            Args::Resolved(args) => args.get(index),
+        }
+    }
+}
+
+/// The arguments and limits used while generating the parts of a model.
+#[derive(Clone, Copy)]
+struct Context<'c> {
+    /// The positional arguments.
+    args: Args<'c>,
+
+    /// The named arguments.
+    named: &'c Map<&'c str, Argument<'c>>,
+
+    /// The index of the argument of each variable.
+    variables: &'c Map<Box<str>, usize>,
+
+    /// The depth of the model being generated.
+    depth: usize,
+
+    /// The maximum depth of nested models.
+    max_depth: usize,
+
+    /// The current item of the innermost repetition block.
+    item: Option<&'c Argument<'c>>,
+
+    /// Whether missing arguments are written as `Argument::Empty` instead of being an error.
+    lenient: bool,
+}
+
+impl<'c> Context<'c> {
+    /// Creates the context of a top-level model.
+    fn new(
+        args: &'c [Argument<'c>],
+        named: &'c Map<&'c str, Argument<'c>>,
+        variables: &'c Map<Box<str>, usize>,
+        max_depth: usize,
+    ) -> Self {
+        Context { args: Args::Slice(args), named, variables, depth: 0, max_depth, item: None, lenient: false }
+    }
+
+    /// Returns the same context, where the positional arguments are resolved from a source.
+    fn resolved(self, args: &'c dyn ArgumentLookup) -> Self {
+        Context { args: Args::Resolved(args), ..self }
+    }
+
+    /// Returns the same context, where missing arguments are written as `Argument::Empty`.
+    fn lenient(self) -> Self {
+        Context { lenient: true, ..self }
+    }
+
+    /// Returns the context of a repetition block, for the given item.
+    fn with_item(self, item: &'c Argument<'c>) -> Self {
+        Context { item: Some(item), ..self }
+    }
+
+    /// Returns the context of a nested model, with its own arguments and variables.
+    /// 
+    /// Returns `None` if the nested model would be deeper than `max_depth`.
+    fn nested(self, model: &'c Model, args: &'c [Argument<'c>], named: &'c Map<&'c str, Argument<'c>>) -> Option<Self> {
+        if self.depth >= self.max_depth {
+            return None;
+        }
+        let variables = model.cached_variables();
+        Some(Context { args: Args::Slice(args), named, variables, depth: self.depth + 1, item: None, ..self })
+    }
+
+    /// Returns the argument of the given variable.
+    /// 
+    /// # Errors
+    /// 
+    /// * `GenerateError::MissingVariable` - The variable is not set by the model.
+    /// * `GenerateError::MissingArgument` - The argument of the variable was not given.
+    fn variable(&self, name: &str) -> Result<&'c Argument<'c>, GenerateError> {
+        let index = *self.variables.get(name).ok_or_else(|| GenerateError::MissingVariable { name: name.into() })?;
+        self.args.get(index).ok_or(GenerateError::MissingArgument { index })
+    }
+}
+
+/// Returns `true` if the argument is given and is not `Argument::Empty`.
+fn is_present(arg: Option<&Argument>) -> bool {
+    !matches!(arg, None | Some(Argument::Empty))
+}
+
+/// Returns the items iterated by a repetition block over the given argument.
+/// 
+/// A missing or empty argument has no item, and an argument that is not a list
+/// is its only item.
+fn items<'c>(arg: Option<&'c Argument<'c>>) -> &'c [Argument<'c>] {
+    match arg {
+        None | Some(Argument::Empty) => &[],
+        Some(Argument::List(items)) => items,
+        Some(arg) => core::slice::from_ref(arg),
+    }
+}
+
+/// Calls `f` on every part, including the parts inside blocks.
+fn visit_parts<F: FnMut(&ModelPart)>(parts: &[ModelPart], f: &mut F) {
+    for part in parts {
+        f(part);
+        if let ModelPart::Conditional { body, .. } | ModelPart::Repetition { body, .. } = part {
+            visit_parts(body, f);
+        }
+    }
+}
+
+/// Checks that the parts are consistent (see `Model::try_from_parts`).
+/// 
+/// `repetition` tells if the parts are inside a repetition block. The variables set
+/// and used by the parts are added to `variables` and `uses`.
+fn check_parts<'p>(
+    parts: &'p [ModelPart],
+    repetition: bool,
+    variables: &mut Set<&'p str>,
+    uses: &mut Vec<&'p str>,
+) -> Result<(), StructureError> {
+    for part in parts {
+        match part {
+            ModelPart::Argument(usize::MAX)
+            | ModelPart::ArgumentOr { index: usize::MAX, .. }
+            | ModelPart::ArgumentSlice { index: usize::MAX, .. }
+            | ModelPart::ArgumentLine(usize::MAX)
+            | ModelPart::Conditional { index: usize::MAX, .. }
+            | ModelPart::Repetition { index: usize::MAX, .. }
+            | ModelPart::SetVar { index: usize::MAX, .. } => return Err(StructureError::IndexOverflow),
+            ModelPart::ArgumentSlice { index, start, end } if start > end => {
+                return Err(StructureError::InvalidSlice { index: *index, start: *start, end: *end });
+            }
+            ModelPart::Item if !repetition => return Err(StructureError::UnexpectedItem),
+            ModelPart::SetVar { name, .. } if !variables.insert(name) => {
+                return Err(StructureError::DuplicateVariable { name: name.clone() });
+            }
+            ModelPart::GetVar(name) => uses.push(name),
+            ModelPart::Conditional { body, .. } => check_parts(body, repetition, variables, uses)?,
+            ModelPart::Repetition { body, .. } => check_parts(body, true, variables, uses)?,
+            _ => (),
+        }
+    }
+    Ok(())
+}
+
+/// A 64-bit FNV-1a hash, which is the same on every run and platform (see `Model::content_hash`).
+struct StableHash(u64);
+
+impl StableHash {
+    /// Creates a hash of nothing.
+    fn new() -> Self {
+        StableHash(0xcbf2_9ce4_8422_2325)
+    }
+
+    /// Adds some bytes to the hash.
+    fn bytes(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    /// Adds a number to the hash, as 8 little-endian bytes.
+    fn number(&mut self, number: usize) {
+        self.bytes(&(number as u64).to_le_bytes());
+    }
+
+    /// Adds a text to the hash, preceded by its length.
+    fn text(&mut self, text: &[u8]) {
+        self.number(text.len());
+        self.bytes(text);
+    }
+}
+
+/// Adds the parts to the hash, each part starting with a tag that identifies its kind.
+/// 
+/// The tags must never change, so the hashes stay the same between versions.
+fn hash_parts(parts: &[ModelPart], hash: &mut StableHash) {
+    hash.number(parts.len());
+    for part in parts {
+        match part {
+            ModelPart::Text(content) => {
+                hash.bytes(&[0]);
+                hash.text(content);
+            }
+            ModelPart::Argument(index) => {
+                hash.bytes(&[1]);
+                hash.number(*index);
+            }
+            ModelPart::NamedArgument(name) => {
+                hash.bytes(&[2]);
+                hash.text(name.as_bytes());
+            }
+            ModelPart::ArgumentOr { index, default } => {
+                hash.bytes(&[3]);
+                hash.number(*index);
+                hash.text(default);
+            }
+            ModelPart::NamedArgumentOr { name, default } => {
+                hash.bytes(&[4]);
+                hash.text(name.as_bytes());
+                hash.text(default);
+            }
+            ModelPart::Conditional { index, body } => {
+                hash.bytes(&[5]);
+                hash.number(*index);
+                hash_parts(body, hash);
+            }
+            ModelPart::Repetition { index, body } => {
+                hash.bytes(&[6]);
+                hash.number(*index);
+                hash_parts(body, hash);
+            }
+            ModelPart::Item => hash.bytes(&[7]),
+            ModelPart::SetVar { name, index } => {
+                hash.bytes(&[8]);
+                hash.text(name.as_bytes());
+                hash.number(*index);
+            }
+            ModelPart::GetVar(name) => {
+                hash.bytes(&[9]);
+                hash.text(name.as_bytes());
+            }
+            ModelPart::ArgumentSlice { index, start, end } => {
+                hash.bytes(&[10]);
+                hash.number(*index);
+                hash.number(*start);
+                hash.number(*end);
+            }
+            ModelPart::ArgumentLine(index) => {
+                hash.bytes(&[11]);
+                hash.number(*index);
+            }
+        }
+    }
+}
+
+/// Merges the adjacent text parts and removes the empty ones, including inside blocks.
+fn compile_parts(parts: Box<[ModelPart]>) -> Box<[ModelPart]> {
+    let mut compiled: Vec<ModelPart> = Vec::with_capacity(parts.len());
+    let mut buffer: Vec<u8> = Vec::new();
+    for part in parts.into_vec() {
+        let part = match part {
+            ModelPart::Text(content) => {
+                buffer.extend_from_slice(&content);
+                continue;
+            }
+            ModelPart::Conditional { index, body } => ModelPart::Conditional { index, body: compile_parts(body) },
+            ModelPart::Repetition { index, body } => ModelPart::Repetition { index, body: compile_parts(body) },
+            part => part,
+        };
+
+        // Add the merged text to the parts (if it's not empty)
+        if !buffer.is_empty() {
+            compiled.push(ModelPart::Text(core::mem::take(&mut buffer).into()));
+        }
+        compiled.push(part);
+    }
+    if !buffer.is_empty() {
+        compiled.push(ModelPart::Text(buffer.into()));
+    }
+    compiled.into()
+}
+
+/// Removes the XML comments from the texts of the parts, including inside blocks (see `Model::strip_comments`).
+fn strip_comment_parts(parts: Box<[ModelPart]>) -> Box<[ModelPart]> {
+    let parts = compile_parts(parts);
+    let comments = find_comments(&parts);
+    let mut stripped: Vec<ModelPart> = Vec::with_capacity(parts.len());
+    for (i, part) in parts.into_vec().into_iter().enumerate() {
+        // The parts of the comments, and the range removed from each of them
+        let mut removed = comments.iter()
+            .filter(|(start, end)| start.0 <= i && i <= end.0)
+            .map(|(start, end)| (if start.0 == i { start.1 } else { 0 }, (end.0 == i).then_some(end.1)));
+        match part {
+            ModelPart::Text(content) => {
+                let mut kept = Vec::with_capacity(content.len());
+                let mut position = 0;
+                for (start, end) in removed {
+                    kept.extend_from_slice(&content[position..start]);
+                    position = end.unwrap_or(content.len());
+                }
+                kept.extend_from_slice(&content[position..]);
+                stripped.push(ModelPart::Text(kept.into()));
+            }
+            ModelPart::Conditional { index, body } => {
+                stripped.push(ModelPart::Conditional { index, body: strip_comment_parts(body) });
+            }
+            ModelPart::Repetition { index, body } => {
+                stripped.push(ModelPart::Repetition { index, body: strip_comment_parts(body) });
+            }
+            part @ ModelPart::SetVar { .. } => stripped.push(part),
+            part => if removed.next().is_none() {
+                stripped.push(part);
+            },
+        }
+    }
+    compile_parts(stripped.into())
+}
+
+/// Returns the removable XML comments of the parts, as the positions of their first
+/// byte and of the byte after their end (the index of a text part and a position in it).
+/// 
+/// A comment containing a block or not closed in the parts is not removable.
+fn find_comments(parts: &[ModelPart]) -> Vec<((usize, usize), (usize, usize))> {
+    /// Where the scanned text is.
+    enum State {
+        Text,
+        Tag { quote: Option<u8> },
+        Cdata,
+        Comment { start: (usize, usize), removable: bool },
+    }
+
+    let mut comments = Vec::new();
+    let mut state = State::Text;
+    for (i, part) in parts.iter().enumerate() {
+        let content = match part {
+            ModelPart::Text(content) => content,
+            ModelPart::Conditional { .. } | ModelPart::Repetition { .. } => {
+                if let State::Comment { removable, .. } = &mut state {
+                    *removable = false;
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        let mut j = 0;
+        while j < content.len() {
+            let rest = &content[j..];
+            match &mut state {
+                State::Text if rest.starts_with(b"<!--") => {
+                    state = State::Comment { start: (i, j), removable: true };
+                    j += 4;
+                    continue;
+                }
+                State::Text if rest.starts_with(b"<![CDATA[") => {
+                    state = State::Cdata;
+                    j += 9;
+                    continue;
+                }
+                State::Text if rest[0] == b'<' => state = State::Tag { quote: None },
+                State::Text => (),
+                State::Tag { quote: Some(quote) } => if rest[0] == *quote {
+                    state = State::Tag { quote: None };
+                },
+                State::Tag { quote } => match rest[0] {
+                    b'"' | b'\'' => *quote = Some(rest[0]),
+                    b'>' => state = State::Text,
+                    _ => (),
+                },
+                State::Cdata => if rest.starts_with(b"]]>") {
+                    state = State::Text;
+                    j += 3;
+                    continue;
+                },
+                State::Comment { start, removable } => if rest.starts_with(b"-->") {
+                    if *removable {
+                        comments.push((*start, (i, j + 3)));
+                    }
+                    state = State::Text;
+                    j += 3;
+                    continue;
+                },
+            }
+            j += 1;
+        }
+    }
+    comments
+}
+
+/// Where the scan of the texts is, for `normalize_whitespace_parts`.
+#[derive(Default)]
+struct WhitespaceScan {
+    /// The number of open elements where whitespace is significant.
+    preserve: usize,
+
+    /// The end of the comment, CDATA section or processing instruction being scanned.
+    verbatim: Option<&'static [u8]>,
+
+    /// The tag being scanned, with the quote of the attribute value being scanned,
+    /// and whether it opens (`Some(true)`) or closes (`Some(false)`) a preserved element.
+    tag: Option<(Option<u8>, Option<bool>)>,
+
+    /// The number of line terminators since the last non-whitespace content.
+    newlines: usize,
+}
+
+/// Normalizes the whitespace of the texts of the parts, including inside blocks (see `Model::normalize_whitespace`).
+fn normalize_whitespace_parts(parts: Box<[ModelPart]>, scan: &mut WhitespaceScan) -> Box<[ModelPart]> {
+    let parts = compile_parts(parts);
+    let mut normalized: Vec<ModelPart> = Vec::with_capacity(parts.len());
+    for part in parts.into_vec() {
+        let content = match part {
+            ModelPart::Text(content) => content,
+            ModelPart::Conditional { index, body } => {
+                scan.newlines = 0;
+                normalized.push(ModelPart::Conditional { index, body: normalize_whitespace_parts(body, scan) });
+                continue;
+            }
+            ModelPart::Repetition { index, body } => {
+                scan.newlines = 0;
+                normalized.push(ModelPart::Repetition { index, body: normalize_whitespace_parts(body, scan) });
+                continue;
+            }
+            part => {
+                if !matches!(part, ModelPart::SetVar { .. }) {
+                    scan.newlines = 0;
+                }
+                normalized.push(part);
+                continue;
+            }
+        };
+
+        let mut result = Vec::with_capacity(content.len());
+        let mut spaces: Vec<u8> = Vec::new();
+        let mut i = 0;
+        while i < content.len() {
+            let c = content[i];
+            let rest = &content[i..];
+
+            // Hold the spaces until the end of the line (or the next content)
+            let trimmable = scan.preserve == 0 && scan.verbatim.is_none() && !matches!(scan.tag, Some((Some(_), _)));
+            if trimmable && matches!(c, b' ' | b'\t' | b'\r') {
+                spaces.push(c);
+                i += 1;
+                continue;
+            }
+            if trimmable && c == b'\n' {
+                let cr = spaces.last() == Some(&b'\r');
+                spaces.clear();
+                scan.newlines += 1;
+                if scan.newlines <= 2 {
+                    result.extend_from_slice(if cr { b"\r\n" } else { b"\n" });
+                }
+                i += 1;
+                continue;
+            }
+            result.append(&mut spaces);
+            scan.newlines = 0;
+
+            // Track the constructs and the elements where whitespace is significant
+            let mut len = 1;
+            match (scan.verbatim, &mut scan.tag) {
+                (Some(end), _) => if rest.starts_with(end) {
+                    scan.verbatim = None;
+                    len = end.len();
+                },
+                (None, Some((Some(quote), _))) => if c == *quote {
+                    scan.tag = scan.tag.map(|(_, preserved)| (None, preserved));
+                },
+                (None, Some((None, preserved))) => match c {
+                    b'"' | b'\'' => scan.tag = Some((Some(c), *preserved)),
+                    b'>' => {
+                        match preserved {
+                            Some(true) if result.last() != Some(&b'/') => scan.preserve += 1,
+                            Some(false) => scan.preserve = scan.preserve.saturating_sub(1),
+                            _ => (),
+                        }
+                        scan.tag = None;
+                    }
+                    _ => (),
+                },
+                (None, None) => if c == b'<' {
+                    if rest.starts_with(b"<!--") {
+                        scan.verbatim = Some(b"-->");
+                        len = 4;
+                    } else if rest.starts_with(b"<![CDATA[") {
+                        scan.verbatim = Some(b"]]>");
+                        len = 9;
+                    } else if rest.starts_with(b"<?") {
+                        scan.verbatim = Some(b"?>");
+                        len = 2;
+                    } else {
+                        let closing = rest.get(1) == Some(&b'/');
+                        let tag = &rest[if closing { 2 } else { 1 }..];
+                        let name_len = tag.iter().position(|c| c.is_ascii_whitespace() || *c == b'/' || *c == b'>').unwrap_or(tag.len());
+                        scan.tag = Some((None, PRESERVED_ELEMENTS.contains(&&tag[..name_len]).then_some(!closing)));
+                    }
+                },
+            }
+            result.extend_from_slice(&rest[..len]);
+            i += len;
+        }
+        result.append(&mut spaces);
+        normalized.push(ModelPart::Text(result.into()));
+    }
+    compile_parts(normalized.into())
+}
+
+/// Changes the index of every positional argument used by the parts, including inside blocks.
+fn map_parts<F: Fn(usize) -> usize>(parts: Box<[ModelPart]>, f: &F) -> Box<[ModelPart]> {
+    parts.into_vec().into_iter().map(|part| match part {
+        ModelPart::Argument(index) => ModelPart::Argument(f(index)),
+        ModelPart::ArgumentOr { index, default } => ModelPart::ArgumentOr { index: f(index), default },
+        ModelPart::ArgumentSlice { index, start, end } => ModelPart::ArgumentSlice { index: f(index), start, end },
+        ModelPart::ArgumentLine(index) => ModelPart::ArgumentLine(f(index)),
+        ModelPart::Conditional { index, body } => ModelPart::Conditional { index: f(index), body: map_parts(body, f) },
+        ModelPart::Repetition { index, body } => ModelPart::Repetition { index: f(index), body: map_parts(body, f) },
+        ModelPart::SetVar { name, index } => ModelPart::SetVar { name, index: f(index) },
+        part => part,
+    }).collect()
+}
+
+/// Renames the variables of the parts, including inside blocks (see `Model::concat`).
+fn rename_variable_parts(parts: Box<[ModelPart]>, renamed: &Map<Box<str>, Box<str>>) -> Box<[ModelPart]> {
+    if renamed.is_empty() {
+        return parts;
+    }
+    let rename = |name: Box<str>| renamed.get(&name).cloned().unwrap_or(name);
+    parts.into_vec().into_iter().map(|part| match part {
+        ModelPart::SetVar { name, index } => ModelPart::SetVar { name: rename(name), index },
+        ModelPart::GetVar(name) => ModelPart::GetVar(rename(name)),
+        ModelPart::Conditional { index, body } => ModelPart::Conditional { index, body: rename_variable_parts(body, renamed) },
+        ModelPart::Repetition { index, body } => ModelPart::Repetition { index, body: rename_variable_parts(body, renamed) },
+        part => part,
+    }).collect()
+}
+
+/// Adds the parts to `bound`, with the given arguments written as text (see `Model::bind`).
+/// 
+/// `variables` are the variables of the whole model, and `item` is the current item
+/// of the innermost repetition block, if it is expanded.
+fn bind_parts(
+    parts: &[ModelPart],
+    args: &[Option<Argument>],
+    variables: &Map<Box<str>, usize>,
+    item: Option<&Argument>,
+    bound: &mut Vec<ModelPart>,
+) -> Result<(), GenerateError> {
+    // The new index of an argument that is not given
+    let reindex = |index: usize| index - args.iter().take(index).filter(|arg| arg.is_some()).count();
+
+    for part in parts {
+        let arg = match part {
+            ModelPart::Argument(index) => match args.get(*index) {
+                Some(Some(arg)) => arg,
+                _ => {
+                    bound.push(ModelPart::Argument(reindex(*index)));
+                    continue;
+                }
+            },
+            ModelPart::ArgumentOr { index, default } => match args.get(*index) {
+                Some(Some(Argument::Empty)) => {
+                    bound.push(ModelPart::Text(default.clone()));
+                    continue;
+                }
+                Some(Some(arg)) => arg,
+                _ => {
+                    bound.push(ModelPart::ArgumentOr { index: reindex(*index), default: default.clone() });
+                    continue;
+                }
+            },
+            ModelPart::ArgumentSlice { index, start, end } => {
+                match args.get(*index) {
+                    Some(Some(arg)) => {
+                        let mut content: Vec<u8> = Vec::new();
+                        let named = Map::new();
+                        let variables = Map::new();
+                        let context = Context::new(&[], &named, &variables, Model::DEFAULT_MAX_DEPTH);
+                        write_argument_slice(arg, (*index, *start, *end), &mut content, context)?;
+                        bound.push(ModelPart::Text(content.into()));
+                    }
+                    _ => bound.push(ModelPart::ArgumentSlice { index: reindex(*index), start: *start, end: *end }),
+                }
+                continue;
+            }
+            ModelPart::ArgumentLine(index) => {
+                match args.get(*index) {
+                    Some(Some(arg)) => {
+                        let mut content: Vec<u8> = Vec::new();
+                        let named = Map::new();
+                        let variables = Map::new();
+                        let context = Context::new(&[], &named, &variables, Model::DEFAULT_MAX_DEPTH);
+                        write_argument_line(arg, &mut content, context)?;
+                        bound.push(ModelPart::Text(content.into()));
+                    }
+                    _ => bound.push(ModelPart::ArgumentLine(reindex(*index))),
+                }
+                continue;
+            }
+            ModelPart::Conditional { index, body } => {
+                match args.get(*index) {
+                    Some(Some(arg)) => if is_present(Some(arg)) {
+                        bind_parts(body, args, variables, item, bound)?;
+                    },
+                    _ => {
+                        let mut inner = Vec::new();
+                        bind_parts(body, args, variables, item, &mut inner)?;
+                        bound.push(ModelPart::Conditional { index: reindex(*index), body: inner.into() });
+                    }
+                }
+                continue;
+            }
+            ModelPart::Repetition { index, body } => {
+                match args.get(*index) {
+                    Some(Some(arg)) => for item in items(Some(arg)) {
+                        bind_parts(body, args, variables, Some(item), bound)?;
+                    },
+                    _ => {
+                        let mut inner = Vec::new();
+                        bind_parts(body, args, variables, None, &mut inner)?;
+                        bound.push(ModelPart::Repetition { index: reindex(*index), body: inner.into() });
+                    }
+                }
+                continue;
+            }
+            ModelPart::Item => match item {
+                Some(item) => item,
+                None => {
+                    bound.push(ModelPart::Item);
+                    continue;
+                }
+            },
+            // A variable of a given argument is written as text, so it is not needed anymore
+            ModelPart::SetVar { name, index } => {
+                if !matches!(args.get(*index), Some(Some(_))) {
+                    bound.push(ModelPart::SetVar { name: name.clone(), index: reindex(*index) });
+                }
+                continue;
+            }
+            ModelPart::GetVar(name) => match variables.get(name).map(|index| args.get(*index)) {
+                Some(Some(Some(arg))) => arg,
+                _ => {
+                    bound.push(ModelPart::GetVar(name.clone()));
+                    continue;
+                }
+            },
+            part => {
+                bound.push(part.clone());
+                continue;
+            }
+        };
+
+        // Write the given argument as text
+        let mut content: Vec<u8> = Vec::new();
+        let named = Map::new();
+        write_argument(arg, &mut content, Context::new(&[], &named, &Map::new(), Model::DEFAULT_MAX_DEPTH))?;
+        bound.push(ModelPart::Text(content.into()));
+    }
+    Ok(())
+}
+
+/// Returns the exact size of the generated parts.
+/// 
+/// Returns `None` if an argument is missing or if the nested models are deeper than the maximum depth.
+fn output_size_parts(parts: &[ModelPart], context: Context) -> Option<usize> {
+    parts.iter().map(|part| {
+        let arg = match part {
+            ModelPart::Text(content) => return Some(content.len()),
+            ModelPart::Argument(index) => match context.args.get(*index) {
+                Some(arg) => arg,
+                None => return context.lenient.then_some(0),
+            },
+            ModelPart::NamedArgument(name) => match context.named.get(&**name) {
+                Some(arg) => arg,
+                None => return context.lenient.then_some(0),
+            },
+            ModelPart::ArgumentOr { index, default } => match context.args.get(*index) {
+                None | Some(Argument::Empty) => return Some(default.len()),
+                Some(arg) => arg,
+            },
+            ModelPart::ArgumentSlice { index, start, end } => match context.args.get(*index) {
+                Some(arg) => {
+                    let len = argument_size(arg, context)?;
+                    return (start <= end && *end <= len).then(|| end - start);
+                }
+                None => return context.lenient.then_some(0),
+            },
+            ModelPart::ArgumentLine(index) => match context.args.get(*index) {
+                Some(arg) => return argument_size(arg, context).map(|len| if len == 0 { 0 } else { len + 1 }),
+                None => return context.lenient.then_some(0),
+            },
+            ModelPart::NamedArgumentOr { name, default } => match context.named.get(&**name) {
+                None | Some(Argument::Empty) => return Some(default.len()),
+                Some(arg) => arg,
+            },
+            ModelPart::Conditional { index, body } => {
+                if !is_present(context.args.get(*index)) {
+                    return Some(0);
+                }
+                return output_size_parts(body, context);
+            }
+            ModelPart::Repetition { index, body } => {
+                return items(context.args.get(*index)).iter()
+                    .map(|item| output_size_parts(body, context.with_item(item)))
+                    .sum();
+            }
+            ModelPart::Item => context.item?,
+            ModelPart::SetVar { .. } => return Some(0),
+            ModelPart::GetVar(name) => match context.variable(name) {
+                Ok(arg) => arg,
+                Err(_) => return context.lenient.then_some(0),
+            },
+        };
+        argument_size(arg, context)
+    }).sum()
+}
+
+/// Returns the exact size of a generated argument.
+fn argument_size(arg: &Argument, context: Context) -> Option<usize> {
+    Some(match arg {
+        Argument::Text(content) | Argument::Bytes(content) => content.len(),
+        Argument::EscapedText(content) => escaped_len(content),
+        Argument::Image(image) => image.content().len(),
+        Argument::OwnedImage(image) => image.content().len(),
+        Argument::Model { model, args } => output_size_parts(&model.parts, context.nested(model, args, &Map::new())?)?,
+        Argument::Lazy(lazy) => lazy.content().len(),
+        Argument::Number(number) => number.to_string().len(),
+        Argument::List(items) => items.iter().map(|item| argument_size(item, context)).sum::<Option<usize>>()?,
+        Argument::Empty => 0,
+    })
+}
+
+/// The destination of generated bytes: any `io::Write` with the `std` feature, a `Vec<u8>` without it.
+trait Output {
+    /// Writes all the bytes to the output.
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), GenerateError>;
+}
+
+#[cfg(feature = "std")]
+impl<W: io::Write + ?Sized> Output for W {
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), GenerateError> {
+        Ok(self.write_all(bytes)?)
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl Output for Vec<u8> {
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), GenerateError> {
+        self.extend_from_slice(bytes);
+        Ok(())
+    }
+}
+
+/// Writes the generated parts to a writer.
+fn write_parts<W: Output + ?Sized>(parts: &[ModelPart], writer: &mut W, context: Context) -> Result<(), GenerateError> {
+    for part in parts {
+        let arg = match part {
+            ModelPart::Text(content) => {
+                writer.write_bytes(content)?;
+                continue;
+            }
+            ModelPart::Argument(index) => match context.args.get(*index) {
+                Some(arg) => arg,
+                None if context.lenient => continue,
+                None => return Err(GenerateError::MissingArgument { index: *index }),
+            },
+            ModelPart::NamedArgument(name) => match context.named.get(&**name) {
+                Some(arg) => arg,
+                None if context.lenient => continue,
+                None => return Err(GenerateError::MissingNamedArgument { name: name.clone() }),
+            },
+            ModelPart::ArgumentOr { index, default } => match context.args.get(*index) {
+                None | Some(Argument::Empty) => {
+                    writer.write_bytes(default)?;
+                    continue;
+                }
+                Some(arg) => arg,
+            },
+            ModelPart::ArgumentSlice { index, start, end } => {
+                match context.args.get(*index) {
+                    Some(arg) => write_argument_slice(arg, (*index, *start, *end), writer, context)?,
+                    None if context.lenient => (),
+                    None => return Err(GenerateError::MissingArgument { index: *index }),
+                }
+                continue;
+            }
+            ModelPart::ArgumentLine(index) => {
+                match context.args.get(*index) {
+                    Some(arg) => write_argument_line(arg, writer, context)?,
+                    None if context.lenient => (),
+                    None => return Err(GenerateError::MissingArgument { index: *index }),
+                }
+                continue;
+            }
+            ModelPart::NamedArgumentOr { name, default } => match context.named.get(&**name) {
+                None | Some(Argument::Empty) => {
+                    writer.write_bytes(default)?;
+                    continue;
+                }
+                Some(arg) => arg,
+            },
+            ModelPart::Conditional { index, body } => {
+                if is_present(context.args.get(*index)) {
+                    write_parts(body, writer, context)?;
+                }
+                continue;
+            }
+            ModelPart::Repetition { index, body } => {
+                for item in items(context.args.get(*index)) {
+                    write_parts(body, writer, context.with_item(item))?;
+                }
+                continue;
+            }
+            ModelPart::Item => context.item.ok_or(GenerateError::MissingItem)?,
+            ModelPart::SetVar { .. } => continue,
+            ModelPart::GetVar(name) => match context.variable(name) {
+                Ok(arg) => arg,
+                Err(_) if context.lenient => continue,
+                Err(err) => return Err(err),
+            },
+        };
+        write_argument(arg, writer, context)?;
+    }
+    Ok(())
+}
+
+/// Writes a generated argument to a writer.
+fn write_argument<W: Output + ?Sized>(arg: &Argument, writer: &mut W, context: Context) -> Result<(), GenerateError> {
+    match arg {
+        Argument::Text(content) | Argument::Bytes(content) => writer.write_bytes(content)?,
+        Argument::EscapedText(content) => write_escaped(writer, content)?,
+        Argument::Image(image) => writer.write_bytes(image.content())?,
+        Argument::OwnedImage(image) => writer.write_bytes(image.content())?,
+        Argument::Model { model, args } => {
+            let named = Map::new();
+            let nested = context.nested(model, args, &named)
+                .ok_or(GenerateError::DepthExceeded { max_depth: context.max_depth })?;
+            write_parts(&model.parts, writer, nested)?;
+        }
+        Argument::Lazy(lazy) => writer.write_bytes(lazy.content())?,
+        Argument::Number(number) => writer.write_bytes(number.to_string().as_bytes())?,
+        Argument::List(items) => {
+            for item in items {
+                write_argument(item, writer, context)?;
+            }
+        }
+        Argument::Empty => (),
+    }
+    Ok(())
+}
+
+/// Writes the bytes `start..end` of a generated argument to a writer (see `ModelPart::ArgumentSlice`).
+/// 
+/// `slice` is the index of the argument, followed by `start` and `end`.
+fn write_argument_slice<W: Output + ?Sized>(
+    arg: &Argument,
+    slice: (usize, usize, usize),
+    writer: &mut W,
+    context: Context,
+) -> Result<(), GenerateError> {
+    let (index, start, end) = slice;
+    let mut buffer = Vec::new();
+    let content = generated_content(arg, &mut buffer, context)?;
+    let slice = content.get(start..end)
+        .ok_or(GenerateError::SliceOutOfRange { index, start, end, len: content.len() })?;
+    writer.write_bytes(slice)?;
+    Ok(())
+}
+
+/// Writes a generated argument followed by a `\n` to a writer, or nothing if it is empty (see `ModelPart::ArgumentLine`).
+fn write_argument_line<W: Output + ?Sized>(arg: &Argument, writer: &mut W, context: Context) -> Result<(), GenerateError> {
+    let mut buffer = Vec::new();
+    let content = generated_content(arg, &mut buffer, context)?;
+    if !content.is_empty() {
+        writer.write_bytes(content)?;
+        writer.write_bytes(b"\n")?;
+    }
+    Ok(())
+}
+
+/// Returns the content of a generated argument.
+/// 
+/// Images are returned directly, the other arguments are generated into `buffer` first.
+fn generated_content<'b>(arg: &'b Argument, buffer: &'b mut Vec<u8>, context: Context) -> Result<&'b [u8], GenerateError> {
+    Ok(match arg {
+        Argument::Image(image) => image.content(),
+        Argument::OwnedImage(image) => image.content(),
+        arg => {
+            write_argument(arg, buffer, context)?;
+            buffer
+        }
+    })
+}
+
+/// A writer flushing the inner writer each time enough bytes are written (see `Model::write_buffered`).
+#[cfg(feature = "std")]
+struct FlushingWriter<'w, W: io::Write> {
+    /// The inner writer.
+    writer: &'w mut W,
+
+    /// The number of bytes written between two flushes.
+    flush_every: usize,
+
+    /// The number of bytes written since the last flush.
+    unflushed: usize,
+}
+
+#[cfg(feature = "std")]
+impl<W: io::Write> io::Write for FlushingWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.writer.write(buf)?;
+        self.unflushed += written;
+        if self.unflushed >= self.flush_every {
+            self.flush()?;
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.unflushed = 0;
+        self.writer.flush()
+    }
+}
+
+/// A writer counting the bytes written to the inner writer (see `Model::write_counted`).
+#[cfg(feature = "std")]
+struct CountingWriter<'w, W: io::Write> {
+    /// The inner writer.
+    writer: &'w mut W,
+
+    /// The number of bytes written.
+    written: usize,
+}
+
+#[cfg(feature = "std")]
+impl<W: io::Write> io::Write for CountingWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.writer.write(buf)?;
+        self.written += written;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// A reader generating the parts of a model when they are reached (see `Model::reader`).
+#[cfg(feature = "std")]
+struct ModelReader<'a> {
+    /// The parts that are not generated yet.
+    parts: &'a [ModelPart],
+
+    /// The positional arguments.
+    args: &'a [Argument<'a>],
+
+    /// The index of the argument of each variable.
+    variables: &'a Map<Box<str>, usize>,
+
+    /// The rest of the current text part.
+    text: &'a [u8],
+
+    /// The generated content of the current part, if it is not a text.
+    buffer: Vec<u8>,
+
+    /// The number of bytes of `buffer` already read.
+    position: usize,
+}
+
+#[cfg(feature = "std")]
+impl io::Read for ModelReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if !self.text.is_empty() {
+                return self.text.read(buf);
+            }
+            if self.position < self.buffer.len() {
+                let len = (&self.buffer[self.position..]).read(buf)?;
+                self.position += len;
+                return Ok(len);
+            }
+
+            // Generate the next part
+            let Some((part, rest)) = self.parts.split_first() else {
+                return Ok(0);
+            };
+            self.parts = rest;
+            if let ModelPart::Text(content) = part {
+                self.text = content;
+                continue;
+            }
+            self.buffer.clear();
+            self.position = 0;
+            let named = Map::new();
+            let context = Context::new(self.args, &named, self.variables, Model::DEFAULT_MAX_DEPTH);
+            write_parts(core::slice::from_ref(part), &mut self.buffer, context).map_err(|err| match err {
+                GenerateError::Io(err) => err,
+                err => io::Error::other(err),
+            })?;
+        }
+    }
+}
+
+impl fmt::Debug for Model {
+    /// Display the parts of a `Model` for debugging.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Model").field("parts", &self.parts).finish()
+    }
+}
+
+impl PartialEq for Model {
+    /// Two models are equal if they have the same parts.
+    fn eq(&self, other: &Self) -> bool {
+        self.parts == other.parts
+    }
+}
+
+impl Eq for Model {}
+
+impl Hash for Model {
+    /// Hashes the parts of a `Model`, as compared by `PartialEq`.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use std::collections::HashSet;
+    /// use svggen::{Model, ModelBuilder, Image, Argument};
+    /// 
+    /// let parsed: Model = "<text>#GET 0</text>".parse().unwrap();
+    /// let built = ModelBuilder::new().text("<text>".as_bytes()).arg(0).text("</text>".as_bytes()).build();
+    /// 
+    /// let models = HashSet::from([parsed.clone(), built, "<g/>".parse().unwrap()]);
+    /// assert_eq!(models.len(), 2);
+    /// assert!(models.contains(&parsed));
+    /// 
+    /// // Rendered images can be cached by model and arguments
+    /// let image = Image::from("<svg/>".as_bytes());
+    /// let key = (parsed.clone(), vec![Argument::Image(&image), Argument::number(1.0)]);
+    /// let images = HashSet::from([key.clone(), key]);
+    /// assert_eq!(images.len(), 1);
+    /// ```
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.parts.hash(state);
+    }
+}
+
+impl fmt::Display for Model {
+    /// Display the source of the model.
+    /// 
+    /// Every argument is written as a `#GET` reference and every `#GET` in the
+    /// text is escaped as `##GET`. Conditional blocks are written with `#IF` and
+    /// `#END` on their own lines, and text lines that would be parsed as directives
+    /// are escaped with a `#`. `Model::load` therefore gives back the same parts
+    /// when the source is valid UTF-8 and the model is unambiguous: adjacent
+    /// text parts are merged, and a text that starts with a digit (or a name
+    /// character after a named argument) or an argument that follows a text
+    /// ending with `#` changes meaning once written. A line terminator is also added
+    /// before a block that does not start a line, and at the end of a block whose
+    /// body does not end a line.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, ModelPart};
+    /// 
+    /// let model = Model::from(vec![
+    ///     ModelPart::from("<svg>\n".as_bytes()),
+    ///     ModelPart::Argument(0),
+    ///     ModelPart::from("\n<text>#GET 1</text>\n".as_bytes()),
+    ///     ModelPart::NamedArgument("footer".into()),
+    ///     ModelPart::from("\n</svg>".as_bytes()),
+    /// ]);
+    /// 
+    /// assert_eq!(model.to_string(), "<svg>\n#GET 0\n<text>##GET 1</text>\n#GET footer\n</svg>");
+    /// ```
+    /// 
+    /// Parsing the source gives back the same model:
+    /// 
+    /// ```
+    /// use svggen::Model;
+    /// 
+    /// let sources = [
+    ///     "",
+    ///     "#GET 0",
+    ///     "<svg>\n#GET 0\n</svg>",
+    ///     "<rect width=\"#GET 0\" height=\"#GET 1\"/>",
+    ///     "#GET 1#GET 0 #GET name\r\n",
+    ///     "##GET 0 ###GET 1 ####GET name #GET",
+    ///     "<svg>\n#IF 0\n<text>#GET 0</text>\n  #IF 1\n#GET 1\n#END\n#END\n</svg>",
+    ///     "##IF 0\n  ###END\n#IF 2\n##END\n#END\n##END",
+    ///     "#FOR 1\n<text>#ITEM##ITEM #GET 0</text>\n#IF 2\n#ITEM\n#END\n#END\n##FOR 0",
+    ///     "##REM #GET 0\n  ###// #GET 1\n##REM\n",
+    ///     "<text>#VAR accent ##VAR accent</text>\n#IF 1\n#SET accent = 1\n#END\n##SET x = 0\n",
+    /// ];
+    /// 
+    /// for source in sources {
+    ///     let model: Model = source.parse().unwrap();
+    ///     let reparsed: Model = model.to_string().parse().unwrap();
+    ///     assert_eq!(reparsed, model);
+    /// }
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_parts(&self.parts, f, &ModelParser::new(), &mut true)
+    }
+}
+
+/// Escapes the `#GET`, `#ITEM` and `#VAR` references of a text.
+fn escape_references(text: &str) -> String {
+    text.replace("#GET", "##GET").replace("#ITEM", "##ITEM").replace("#VAR", "##VAR")
+}
+
+/// Writes the source of the parts.
+/// 
+/// `parser` is the default parser, used to find the lines to escape. `line_start`
+/// tells if the source written so far ends with a line terminator (or is empty),
+/// and is updated by the written source.
+fn fmt_parts(parts: &[ModelPart], f: &mut fmt::Formatter, parser: &ModelParser, line_start: &mut bool) -> fmt::Result {
+    for (i, part) in parts.iter().enumerate() {
+        match part {
+            ModelPart::Text(content) => {
+                // The last line is complete if the next part is written on its own line
+                let complete = matches!(
+                    parts.get(i + 1),
+                    None | Some(
+                        ModelPart::Conditional { .. }
+                        | ModelPart::Repetition { .. }
+                        | ModelPart::SetVar { .. }
+                        | ModelPart::ArgumentLine(_)
+                    ),
+                );
+                let content = String::from_utf8_lossy(content);
+                for line in content.split_inclusive('\n') {
+                    // A comment only needs to start a line, the other directives must be the whole line
+                    let escape = *line_start && if line.ends_with('\n') || complete {
+                        parser.parse_escaped_directive(line.trim_ascii().as_bytes()).is_some()
+                    } else {
+                        matches!(parser.parse_escaped_directive(line.trim_ascii_start().as_bytes()), Some(Directive::Comment))
+                    };
+                    if escape {
+                        // Escape the line so it is not parsed as a directive
+                        let indent = line.len() - line.trim_ascii_start().len();
+                        write!(f, "{}#{}", &line[..indent], escape_references(&line[indent..]))?;
+                    } else {
+                        write!(f, "{}", escape_references(line))?;
+                    }
+                    *line_start = line.ends_with('\n');
+                }
+                continue;
+            }
+            ModelPart::Argument(index) => write!(f, "#GET {}", index)?,
+            ModelPart::NamedArgument(name) => write!(f, "#GET {}", name)?,
+            ModelPart::ArgumentOr { index, default } => {
+                write!(f, "#GET {}|{}|", index, String::from_utf8_lossy(default))?;
+            }
+            ModelPart::ArgumentSlice { index, start, end } => write!(f, "#GET {}[{}..{}]", index, start, end)?,
+            ModelPart::NamedArgumentOr { name, default } => {
+                write!(f, "#GET {}|{}|", name, String::from_utf8_lossy(default))?;
+            }
+            ModelPart::Item => write!(f, "#ITEM")?,
+            ModelPart::GetVar(name) => write!(f, "#VAR {}", name)?,
+            ModelPart::ArgumentLine(index) => {
+                if !*line_start {
+                    writeln!(f)?;
+                }
+                writeln!(f, "#GETLINE {}", index)?;
+                *line_start = true;
+                continue;
+            }
+            ModelPart::SetVar { name, index } => {
+                if !*line_start {
+                    writeln!(f)?;
+                }
+                writeln!(f, "#SET {} = {}", name, index)?;
+                *line_start = true;
+                continue;
+            }
+            ModelPart::Conditional { index, body } | ModelPart::Repetition { index, body } => {
+                if !*line_start {
+                    writeln!(f)?;
+                }
+                match part {
+                    ModelPart::Conditional { .. } => writeln!(f, "#IF {}", index)?,
+                    _ => writeln!(f, "#FOR {}", index)?,
+                }
+                *line_start = true;
+                fmt_parts(body, f, parser, line_start)?;
+                if !*line_start {
+                    writeln!(f)?;
+                }
+                writeln!(f, "#END")?;
+                *line_start = true;
+                continue;
+            }
+        }
+        *line_start = false;
+    }
+    Ok(())
+}
+
+impl From<Box<[ModelPart]>> for Model {
+    /// Creates a new model from the given parts.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `parts` - The parts of the model.
+    fn from(parts: Box<[ModelPart]>) -> Self {
+        Model { parts, arguments: Cache::new(), variables: Cache::new() }
+    }
+}
+
+impl From<Vec<ModelPart>> for Model {
+    /// Creates a new model from the given parts.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `parts` - The parts of the model.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, ModelPart};
+    /// 
+    /// let model = Model::from(vec![
+    ///     ModelPart::from("Hello ".as_bytes()),
+    ///     ModelPart::Argument(0),
+    ///     ModelPart::from("!".as_bytes()),
+    /// ]);
+    /// 
+    /// assert_eq!(model.parts(), &[
+    ///     ModelPart::Text(b"Hello ".to_vec().into()),
+    ///     ModelPart::Argument(0),
+    ///     ModelPart::Text(b"!".to_vec().into()),
+    /// ]);
+    /// ```
+    fn from(parts: Vec<ModelPart>) -> Self {
+        Model::from(parts.into_boxed_slice())
+    }
+}
+
+impl From<&[ModelPart]> for Model {
+    /// Creates a new model from a copy of the given parts.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `parts` - The parts of the model.
+    fn from(parts: &[ModelPart]) -> Self {
+        Model::from(Box::<[ModelPart]>::from(parts))
+    }
+}
+
+impl<const N: usize> From<[ModelPart; N]> for Model {
+    /// Creates a new model from the given parts.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `parts` - The parts of the model.
+    fn from(parts: [ModelPart; N]) -> Self {
+        Model::from(Box::<[ModelPart]>::from(parts))
+    }
+}
+
+impl<'a> IntoIterator for &'a Model {
+    type Item = &'a ModelPart;
+    type IntoIter = core::slice::Iter<'a, ModelPart>;
+
+    /// Iterates over the top-level parts of the model (see `Model::parts`).
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, ModelPart};
+    /// 
+    /// let model: Model = "<rect width=\"#GET 0\" height=\"#GET 1\"/>".parse().unwrap();
+    /// 
+    /// let mut count = 0;
+    /// for part in &model {
+    ///     if let ModelPart::Argument(_) = part {
+    ///         count += 1;
+    ///     }
+    /// }
+    /// assert_eq!(count, 2);
+    /// assert_eq!((&model).into_iter().len(), model.parts().len());
+    /// ```
+    fn into_iter(self) -> Self::IntoIter {
+        self.parts.iter()
+    }
+}
+
+/// A builder used to create a model from text and arguments.
+/// 
+/// Consecutive texts are merged into a single text part.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use svggen::{Model, ModelBuilder};
+/// 
+/// let model = ModelBuilder::new()
+///     .text("Hello ".as_bytes())
+///     .arg(0)
+///     .text(" ".as_bytes())
+///     .text("!".as_bytes())
+///     .build();
+/// 
+/// assert_eq!(model, "Hello #GET 0 !".parse::<Model>().unwrap());
+/// assert_eq!(model.parts().len(), 3);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ModelBuilder {
+    /// The parts already built.
+    parts: Vec<ModelPart>,
+
+    /// The text not yet added to the parts.
+    text: Vec<u8>,
+}
+
+impl ModelBuilder {
+    /// Creates a new empty builder.
+    pub fn new() -> Self {
+        ModelBuilder::default()
+    }
+
+    /// Adds some text, merged with the previous text if there is one.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `content` - The text to add.
+    pub fn text<T: Into<Box<[u8]>>>(mut self, content: T) -> Self {
+        self.text.extend_from_slice(&content.into());
+        self
+    }
+
+    /// Adds a positional argument.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `index` - The index of the argument.
+    pub fn arg(self, index: usize) -> Self {
+        self.part(ModelPart::Argument(index))
+    }
+
+    /// Adds a named argument.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `name` - The name of the argument.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, ModelBuilder};
+    /// 
+    /// let model = ModelBuilder::new().text("<text>".as_bytes()).named("title").text("</text>".as_bytes()).build();
+    /// assert_eq!(model, "<text>#GET title</text>".parse::<Model>().unwrap());
+    /// ```
+    pub fn named(self, name: &str) -> Self {
+        self.part(ModelPart::NamedArgument(name.into()))
+    }
+
+    /// Adds any part, texts being merged with the previous text.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `part` - The part to add.
+    pub fn part(mut self, part: ModelPart) -> Self {
+        if let ModelPart::Text(content) = part {
+            return self.text(content);
+        }
+        if !self.text.is_empty() {
+            self.parts.push(ModelPart::Text(core::mem::take(&mut self.text).into()));
+        }
+        self.parts.push(part);
+        self
+    }
+
+    /// Creates the model.
+    pub fn build(mut self) -> Model {
+        if !self.text.is_empty() {
+            self.parts.push(ModelPart::Text(self.text.into()));
+        }
+        Model::from(self.parts)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Readable for Model {
+    /// The error returned when the model is invalid.
+    type ParseError = ParseError;
+
+    /// Creates a new model from a reader.
+    /// 
+    /// Every `#GET n` found in the text is replaced by the argument of index `n`,
+    /// wherever it is in the line. Any number of spaces or tabs can separate `#GET`
+    /// from the index, and the reference ends at the first non-digit: what follows
+    /// (whitespace, a unit, a comment...) is kept as text and is never an error.
+    /// 
+    /// Every `#GET name` is replaced by the named argument `name`, a name being
+    /// an ASCII letter or `_` followed by ASCII letters, digits or `_`.
+    /// 
+    /// A bare `#GET`, directly followed by anything but a space, a tab, a digit or
+    /// a name character (for example `<text>#GET</text>`), is numbered automatically:
+    /// the first bare `#GET` of the source is the argument 0, the next one the
+    /// argument 1, and so on, in the order of the source (including inside blocks
+    /// and included contents). As in `format!`, the references with an index do not
+    /// change this counter, so `#GET #GET 0 #GET` uses the arguments 0, 0 and 1.
+    /// 
+    /// A reference directly followed by `|default|` uses the text `default` when
+    /// the argument is missing or `Argument::Empty`. The default text is kept as
+    /// is and cannot contain a `|` or a line terminator.
+    /// 
+    /// A line containing only `#DEFAULT n text` gives the default text `text` (the
+    /// rest of the line after the whitespace following `n`, which can be empty) to
+    /// every `#GET n` of the model without its own default. The line can be anywhere
+    /// in the model, even after the references, and is removed. An argument can
+    /// only have one `#DEFAULT`, and its text cannot contain a `|`. The references
+    /// become `ModelPart::ArgumentOr` parts, so the `#DEFAULT` lines are not kept
+    /// when the model is displayed.
+    /// 
+    /// Every `##GET` is replaced by a literal `#GET`. A text can therefore be
+    /// escaped unambiguously by adding a `#` before each `#GET` it contains.
+    /// 
+    /// A line containing only `#GETLINE n` is replaced by the argument of index `n`
+    /// followed by a `\n`, or removed if the argument is empty. A `#GET n` is
+    /// always replaced in place, so on its own line it keeps the line terminators
+    /// around it.
+    /// 
+    /// A line containing only `#IF n` starts a conditional block, closed by a line
+    /// containing only `#END`. The parts of the block are only generated if the
+    /// argument of index `n` is present and not `Argument::Empty`. Blocks can be
+    /// nested, and the directive lines are removed with their line terminator.
+    /// A directive line can be escaped by adding a `#` before it (`##IF 0`).
+    /// 
+    /// A line containing only `#FOR n` starts a repetition block, also closed by
+    /// `#END`. The parts of the block are generated once for each item of the
+    /// argument of index `n` (an `Argument::List`), and every `#ITEM` inside the
+    /// block is replaced by the current item. A missing or empty argument has no
+    /// item, and any other argument is its only item. In nested blocks, `#ITEM` is
+    /// the item of the innermost `#FOR`. The `#GET` references inside a block are
+    /// not changed by the loop: they still refer to the arguments of the model.
+    /// Every `##ITEM` is replaced by a literal `#ITEM`.
+    /// 
+    /// A line containing only `#SET name = n` sets the variable `name` to the argument
+    /// of index `n` (the spaces around `=` are optional), and every `#VAR name` is
+    /// replaced by that argument, so an argument used several times can be named.
+    /// A variable is set for the whole model: it can be used before its `#SET` and
+    /// outside of the block containing it, but not by the nested models. A variable
+    /// cannot be set twice, and every `#VAR` must use a variable set by the model.
+    /// Every `##VAR` is replaced by a literal `#VAR`.
+    /// 
+    /// A line whose trimmed content starts with `#REM ` or `#//` is a comment: it is
+    /// removed with its line terminator, so it does not leave a blank line.
+    /// 
+    /// The rest of the text is kept byte for byte, including its line terminators
+    /// (`\n` or `\r\n`), the final line terminator if there is one, and the bytes
+    /// that are not valid UTF-8.
+    /// 
+    /// This is a shortcut for `ModelParser::new().parse(reader)`, use a `ModelParser`
+    /// to write the directives with another syntax (for example `{{GET 0}}`).
+    /// 
+    /// # Arguments
+    /// 
+    /// * `reader` - The reader to read the model from.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use rutil::read::Readable;
+    /// use svggen::{Model, ModelPart, Image, Argument};
+    /// 
+    /// let mut data = "<svg>\n#GET 0\n</svg>".as_bytes();
+    /// 
+    /// // data implements `io::Read` so we can use it as a reader
+    /// let model = Model::load(&mut data).unwrap();
+    /// 
+    /// assert_eq!(model.parts(), &[
+    ///     ModelPart::Text(b"<svg>\n".to_vec().into()),
+    ///     ModelPart::Argument(0),
+    ///     ModelPart::Text(b"\n</svg>".to_vec().into()),
+    /// ]);
+    /// ```
+    /// 
+    /// Multiple references can be used in the same line, even inside attributes:
+    /// 
+    /// ```
+    /// use rutil::read::Readable;
+    /// use svggen::{Model, ModelPart};
+    /// 
+    /// let mut data = "<rect width=\"#GET 0\" height=\"#GET 1\"/>".as_bytes();
+    /// let model = Model::load(&mut data).unwrap();
+    /// 
+    /// assert_eq!(model.parts(), &[
+    ///     ModelPart::Text(b"<rect width=\"".to_vec().into()),
+    ///     ModelPart::Argument(0),
+    ///     ModelPart::Text(b"\" height=\"".to_vec().into()),
+    ///     ModelPart::Argument(1),
+    ///     ModelPart::Text(b"\"/>".to_vec().into()),
+    /// ]);
+    /// ```
+    /// 
+    /// A `##GET` is an escaped reference and is collapsed into a literal `#GET`.
+    /// To get a literal `##GET`, write `###GET`, and so on:
+    /// 
+    /// ```
+    /// use rutil::read::Readable;
+    /// use svggen::{Model, ModelPart};
+    /// 
+    /// let mut data = "##GET 0\n<text>##GET 1 and #GET 2</text>".as_bytes();
+    /// let model = Model::load(&mut data).unwrap();
+    /// 
+    /// assert_eq!(model.parts(), &[
+    ///     ModelPart::Text(b"#GET 0\n<text>#GET 1 and ".to_vec().into()),
+    ///     ModelPart::Argument(2),
+    ///     ModelPart::Text(b"</text>".to_vec().into()),
+    /// ]);
+    /// ```
+    /// 
+    /// Positional and named references can be used in the same model:
+    /// 
+    /// ```
+    /// use rutil::read::Readable;
+    /// use svggen::{Model, ModelPart};
+    /// 
+    /// let mut data = "<text fill=\"#GET color\">#GET 0</text>".as_bytes();
+    /// let model = Model::load(&mut data).unwrap();
+    /// 
+    /// assert_eq!(model.parts(), &[
+    ///     ModelPart::Text(b"<text fill=\"".to_vec().into()),
+    ///     ModelPart::NamedArgument("color".into()),
+    ///     ModelPart::Text(b"\">".to_vec().into()),
+    ///     ModelPart::Argument(0),
+    ///     ModelPart::Text(b"</text>".to_vec().into()),
+    /// ]);
+    /// ```
+    /// 
+    /// A default value can be given between two `|` right after a reference:
+    /// 
+    /// ```
+    /// use rutil::read::Readable;
+    /// use svggen::{Model, ModelPart, Argument};
+    /// 
+    /// let mut data = "<text>#GET 0|Untitled|</text> | #GET 1|".as_bytes();
+    /// let model = Model::load(&mut data).unwrap();
+    /// 
+    /// assert_eq!(model.parts(), &[
+    ///     ModelPart::Text(b"<text>".to_vec().into()),
+    ///     ModelPart::ArgumentOr { index: 0, default: b"Untitled".to_vec().into() },
+    ///     ModelPart::Text(b"</text> | ".to_vec().into()),
+    ///     ModelPart::Argument(1),
+    ///     ModelPart::Text(b"|".to_vec().into()),
+    /// ]);
+    /// 
+    /// let model: Model = "<text>#GET 0|Untitled|</text>".parse().unwrap();
+    /// 
+    /// // The argument is given
+    /// let image = model.generate(&[Argument::from("Title".as_bytes())]).unwrap();
+    /// assert_eq!(image.content(), b"<text>Title</text>");
+    /// 
+    /// // The argument is empty
+    /// let image = model.generate(&[Argument::Empty]).unwrap();
+    /// assert_eq!(image.content(), b"<text>Untitled</text>");
+    /// 
+    /// // The argument is missing
+    /// let image = model.generate(&[]).unwrap();
+    /// assert_eq!(image.content(), b"<text>Untitled</text>");
+    /// assert_eq!(model.output_size(&[]), Some(image.content().len()));
+    /// ```
+    /// 
+    /// A variable can be used several times, and anywhere in the model:
+    /// 
+    /// ```
+    /// use svggen::{Model, ModelPart, Argument, ParseErrorKind};
+    /// 
+    /// let source = "<rect fill=\"#VAR accent\"/>\n#SET accent = 0\n<text fill=\"#VAR accent\">#GET 1</text>";
+    /// let model: Model = source.parse().unwrap();
+    /// 
+    /// assert_eq!(model.parts(), &[
+    ///     ModelPart::Text(b"<rect fill=\"".to_vec().into()),
+    ///     ModelPart::GetVar("accent".into()),
+    ///     ModelPart::Text(b"\"/>\n".to_vec().into()),
+    ///     ModelPart::SetVar { name: "accent".into(), index: 0 },
+    ///     ModelPart::Text(b"<text fill=\"".to_vec().into()),
+    ///     ModelPart::GetVar("accent".into()),
+    ///     ModelPart::Text(b"\">".to_vec().into()),
+    ///     ModelPart::Argument(1),
+    ///     ModelPart::Text(b"</text>".to_vec().into()),
+    /// ]);
+    /// 
+    /// let args = [Argument::from("red".as_bytes()), Argument::from("Hello".as_bytes())];
+    /// let image = model.generate(&args).unwrap();
+    /// assert_eq!(image.content(), b"<rect fill=\"red\"/>\n<text fill=\"red\">Hello</text>");
+    /// assert_eq!(model.output_size(&args), Some(image.content().len()));
+    /// 
+    /// let error = "#SET a = 0\n<text>#VAR b</text>\n".parse::<Model>().unwrap_err();
+    /// assert_eq!((error.line(), error.kind()), (2, ParseErrorKind::UndefinedVariable));
+    /// 
+    /// let error = "#SET a = 0\n#IF 1\n#SET a = 1\n#END\n".parse::<Model>().unwrap_err();
+    /// assert_eq!((error.line(), error.kind()), (3, ParseErrorKind::DuplicateVariable));
+    /// ```
+    /// 
+    /// Line terminators are preserved, so Windows-authored models generate the same bytes:
+    /// 
+    /// ```
+    /// use rutil::read::Readable;
+    /// use svggen::{Model, ModelPart};
+    /// 
+    /// let mut data = "<svg>\r\n#GET 0\r\n</svg>\r\n".as_bytes();
+    /// let model = Model::load(&mut data).unwrap();
+    /// 
+    /// assert_eq!(model.parts(), &[
+    ///     ModelPart::Text(b"<svg>\r\n".to_vec().into()),
+    ///     ModelPart::Argument(0),
+    ///     ModelPart::Text(b"\r\n</svg>\r\n".to_vec().into()),
+    /// ]);
+    /// ```
+    /// 
+    /// The model does not need to be valid UTF-8, only the directives need to be ASCII:
+    /// 
+    /// ```
+    /// use rutil::read::Readable;
+    /// use svggen::{Model, ModelPart, Argument};
+    /// 
+    /// let mut data: &[u8] = b"<!-- \xff\xfe -->\n<text>#GET 0</text>";
+    /// let model = Model::load(&mut data).unwrap();
+    /// 
+    /// assert_eq!(model.parts(), &[
+    ///     ModelPart::Text(b"<!-- \xff\xfe -->\n<text>".to_vec().into()),
+    ///     ModelPart::Argument(0),
+    ///     ModelPart::Text(b"</text>".to_vec().into()),
+    /// ]);
+    /// 
+    /// let image = model.generate(&[Argument::from("Hi".as_bytes())]).unwrap();
+    /// assert_eq!(image.content(), b"<!-- \xff\xfe -->\n<text>Hi</text>");
+    /// ```
+    /// 
+    /// Many text and argument parts can be interleaved:
+    /// 
+    /// ```
+    /// use rutil::read::Readable;
+    /// use svggen::{Model, ModelPart};
+    /// 
+    /// let source: String = (0..100).map(|i| format!("<g id=\"{}\">\n#GET {}\n</g>", i, i)).collect();
+    /// let model = Model::load(&mut source.as_bytes()).unwrap();
+    /// 
+    /// assert_eq!(model.parts().len(), 201);
+    /// for (i, part) in model.parts().iter().enumerate() {
+    ///     let expected = match i {
+    ///         0 => ModelPart::Text(b"<g id=\"0\">\n".to_vec().into()),
+    ///         200 => ModelPart::Text(b"\n</g>".to_vec().into()),
+    ///         _ if i % 2 == 1 => ModelPart::Argument(i / 2),
+    ///         _ => ModelPart::Text(format!("\n</g><g id=\"{}\">\n", i / 2).into_bytes().into()),
+    ///     };
+    ///     assert_eq!(part, &expected);
+    /// }
+    /// ```
+    /// 
+    /// The bare references are numbered in order, independently of the other references:
+    /// 
+    /// ```
+    /// use svggen::{Model, ModelPart, Argument};
+    /// 
+    /// let model: Model = "<text>#GET</text><text>#GET</text><text>#GET</text>".parse().unwrap();
+    /// assert_eq!(model.arguments().into_iter().collect::<Vec<_>>(), [0, 1, 2]);
+    /// 
+    /// let args = [Argument::from("a"), Argument::from("b"), Argument::from("c")];
+    /// assert_eq!(model.generate(&args).unwrap().content(), b"<text>a</text><text>b</text><text>c</text>");
+    /// 
+    /// let model: Model = "#GET|none| #GET 0\n#GET".parse().unwrap();
+    /// assert_eq!(model.parts(), &[
+    ///     ModelPart::ArgumentOr { index: 0, default: b"none".to_vec().into() },
+    ///     ModelPart::Text(b" ".to_vec().into()),
+    ///     ModelPart::Argument(0),
+    ///     ModelPart::Text(b"\n".to_vec().into()),
+    ///     ModelPart::Argument(1),
+    /// ]);
+    /// ```
+    /// 
+    /// The default texts can be declared once for the whole model:
+    /// 
+    /// ```
+    /// use svggen::{Model, ModelPart, Argument, ParseErrorKind};
+    /// 
+    /// let model: Model = "#DEFAULT 0 Untitled\n<title>#GET 0</title>\n<text>#GET 0|-|</text>\n#DEFAULT 1 \n#GET 1"
+    ///     .parse()
+    ///     .unwrap();
+    /// assert_eq!(model.parts()[1], ModelPart::ArgumentOr { index: 0, default: b"Untitled".to_vec().into() });
+    /// 
+    /// let image = model.generate(&[]).unwrap();
+    /// assert_eq!(image.content(), b"<title>Untitled</title>\n<text>-</text>\n");
+    /// 
+    /// let image = model.generate(&[Argument::from("Map"), Argument::from("!")]).unwrap();
+    /// assert_eq!(image.content(), b"<title>Map</title>\n<text>Map</text>\n!");
+    /// 
+    /// let error = "#DEFAULT 0 a\n#DEFAULT 0 b\n".parse::<Model>().unwrap_err();
+    /// assert_eq!((error.line(), error.kind()), (2, ParseErrorKind::DuplicateDefault));
+    /// ```
+    /// 
+    /// A `#GET` directly followed by a letter or a digit, or followed by whitespace
+    /// without an index or a name, is kept as text:
+    /// 
+    /// ```
+    /// use rutil::read::Readable;
+    /// use svggen::{Model, ModelPart};
+    /// 
+    /// let mut data = "#GETX #GET -1 #GET 2px".as_bytes();
+    /// let model = Model::load(&mut data).unwrap();
+    /// 
+    /// assert_eq!(model.parts(), &[
+    ///     ModelPart::Text(b"#GETX #GET -1 ".to_vec().into()),
+    ///     ModelPart::Argument(2),
+    ///     ModelPart::Text(b"px".to_vec().into()),
+    /// ]);
+    /// ```
+    /// 
+    /// Extra spaces and tabs around an index are kept out of the reference:
+    /// 
+    /// ```
+    /// use rutil::read::Readable;
+    /// use svggen::{Model, ModelPart};
+    /// 
+    /// let mut data = "#GET  0\t<!-- title -->\n#GET \t1\t\n#IF  1\t\n<g/>\n#END\n".as_bytes();
+    /// let model = Model::load(&mut data).unwrap();
+    /// 
+    /// assert_eq!(model.parts(), &[
+    ///     ModelPart::Argument(0),
+    ///     ModelPart::Text(b"\t<!-- title -->\n".to_vec().into()),
+    ///     ModelPart::Argument(1),
+    ///     ModelPart::Text(b"\t\n".to_vec().into()),
+    ///     ModelPart::Conditional { index: 1, body: vec![ModelPart::Text(b"<g/>\n".to_vec().into())].into() },
+    /// ]);
+    /// ```
+    /// 
+    /// A conditional block is only generated if its argument is present and not empty:
+    /// 
+    /// ```
+    /// use rutil::read::Readable;
+    /// use svggen::{Model, ModelPart, Argument};
+    /// 
+    /// let mut data = "<svg>\n#IF 1\n<text>#GET 1</text>\n#END\n</svg>".as_bytes();
+    /// let model = Model::load(&mut data).unwrap();
+    /// 
+    /// assert_eq!(model.parts(), &[
+    ///     ModelPart::Text(b"<svg>\n".to_vec().into()),
+    ///     ModelPart::Conditional {
+    ///         index: 1,
+    ///         body: vec![
+    ///             ModelPart::Text(b"<text>".to_vec().into()),
+    ///             ModelPart::Argument(1),
+    ///             ModelPart::Text(b"</text>\n".to_vec().into()),
+    ///         ].into(),
+    ///     },
+    ///     ModelPart::Text(b"</svg>".to_vec().into()),
+    /// ]);
+    /// 
+    /// let title = Argument::from("Title".as_bytes());
+    /// let subtitle = Argument::from("Subtitle".as_bytes());
+    /// 
+    /// let image = model.generate(&[title.clone(), subtitle]).unwrap();
+    /// assert_eq!(image.content(), b"<svg>\n<text>Subtitle</text>\n</svg>");
+    /// 
+    /// let image = model.generate(&[title.clone(), Argument::Empty]).unwrap();
+    /// assert_eq!(image.content(), b"<svg>\n</svg>");
+    /// 
+    /// let image = model.generate(&[title]).unwrap();
+    /// assert_eq!(image.content(), b"<svg>\n</svg>");
+    /// assert_eq!(model.output_size(&[]), Some(image.content().len()));
+    /// ```
+    /// 
+    /// Blocks can be nested, and an escaped directive is kept as text:
+    /// 
+    /// ```
+    /// use svggen::{Model, Argument};
+    /// 
+    /// let model: Model = "#IF 0\nA\n  #IF 1\n  B\n  #END\n##END\n#END\n".parse().unwrap();
+    /// let arg = Argument::from("x".as_bytes());
+    /// 
+    /// let image = model.generate(&[arg.clone(), arg.clone()]).unwrap();
+    /// assert_eq!(image.content(), b"A\n  B\n#END\n");
+    /// 
+    /// let image = model.generate(&[arg, Argument::Empty]).unwrap();
+    /// assert_eq!(image.content(), b"A\n#END\n");
+    /// 
+    /// let image = model.generate(&[]).unwrap();
+    /// assert_eq!(image.content(), b"");
+    /// ```
+    /// 
+    /// A repetition block is generated once for each item of a list:
+    /// 
+    /// ```
+    /// use rutil::read::Readable;
+    /// use svggen::{Model, ModelPart, Argument};
+    /// 
+    /// let mut data = "<g fill=\"#GET 1\">\n#FOR 0\n<text>#ITEM</text>\n#END\n</g>".as_bytes();
+    /// let model = Model::load(&mut data).unwrap();
+    /// 
+    /// assert_eq!(model.parts()[3], ModelPart::Repetition {
+    ///     index: 0,
+    ///     body: vec![
+    ///         ModelPart::Text(b"<text>".to_vec().into()),
+    ///         ModelPart::Item,
+    ///         ModelPart::Text(b"</text>\n".to_vec().into()),
+    ///     ].into(),
+    /// });
+    /// 
+    /// let items = Argument::List(vec![
+    ///     Argument::from("A".as_bytes()),
+    ///     Argument::from("B".as_bytes()),
+    /// ]);
+    /// let color = Argument::from("red".as_bytes());
+    /// let image = model.generate(&[items, color.clone()]).unwrap();
+    /// assert_eq!(image.content(), b"<g fill=\"red\">\n<text>A</text>\n<text>B</text>\n</g>");
+    /// 
+    /// // An empty list has no item
+    /// let args = [Argument::List(vec![]), color];
+    /// let image = model.generate(&args).unwrap();
+    /// assert_eq!(image.content(), b"<g fill=\"red\">\n</g>");
+    /// assert_eq!(model.output_size(&args), Some(image.content().len()));
+    /// ```
+    /// 
+    /// Comments are removed from the model and never reach the generated image:
+    /// 
+    /// ```
+    /// use rutil::read::Readable;
+    /// use svggen::{Model, ModelPart, Argument};
+    /// 
+    /// let mut data = "<svg>\n  #REM The title of the card\n<text>#GET 0</text>\n#// secret\n</svg>\n".as_bytes();
+    /// let model = Model::load(&mut data).unwrap();
+    /// 
+    /// assert_eq!(model.parts(), &[
+    ///     ModelPart::Text(b"<svg>\n<text>".to_vec().into()),
+    ///     ModelPart::Argument(0),
+    ///     ModelPart::Text(b"</text>\n</svg>\n".to_vec().into()),
+    /// ]);
+    /// 
+    /// let image = model.generate(&[Argument::from("Hello".as_bytes())]).unwrap();
+    /// assert_eq!(image.content(), b"<svg>\n<text>Hello</text>\n</svg>\n");
+    /// 
+    /// let content = String::from_utf8(image.content().to_vec()).unwrap();
+    /// assert!(!content.contains("title of the card") && !content.contains("secret"));
+    /// 
+    /// // `#REMOVE` is not a comment, and an escaped comment is kept as text
+    /// let model: Model = "#REMOVE\n##REM kept\n".parse().unwrap();
+    /// assert_eq!(model.parts(), &[ModelPart::Text(b"#REMOVE\n#REM kept\n".to_vec().into())]);
+    /// ```
+    /// 
+    /// Nested repetition blocks use the item of the innermost block:
+    /// 
+    /// ```
+    /// use svggen::{Model, Argument};
+    /// 
+    /// let model: Model = "#FOR 0\n[#ITEM:\n#FOR 1\n#ITEM##ITEM,\n#END\n]\n#END\n".parse().unwrap();
+    /// 
+    /// let args = [
+    ///     Argument::List(vec![Argument::from("a".as_bytes()), Argument::from("b".as_bytes())]),
+    ///     Argument::List(vec![Argument::from("1".as_bytes()), Argument::from("2".as_bytes())]),
+    /// ];
+    /// let image = model.generate(&args).unwrap();
+    /// assert_eq!(image.content(), b"[a:\n1#ITEM,\n2#ITEM,\n]\n[b:\n1#ITEM,\n2#ITEM,\n]\n");
+    /// ```
+    /// 
+    /// # Errors
+    /// 
+    /// * `ReadError::Parse(ParseError)` - The model is invalid, the error gives the line and the kind of error:
+    ///   * `ParseErrorKind::UnclosedBlock` - A block is not closed by an `#END`.
+    ///   * `ParseErrorKind::UnexpectedEnd` - An `#END` is found outside of any block.
+    ///   * `ParseErrorKind::UnexpectedItem` - An `#ITEM` is found outside of any `#FOR` block.
+    ///   * `ParseErrorKind::UndefinedVariable` - A `#VAR` uses a variable that is not set by any `#SET`.
+    ///   * `ParseErrorKind::DuplicateVariable` - A variable is set by more than one `#SET`.
+    ///   * `ParseErrorKind::DuplicateDefault` - An argument has its default text set by more than one `#DEFAULT`.
+    ///   * `ParseErrorKind::InvalidDefault` - A `#DEFAULT` text contains a `|`.
+    ///   * `ParseErrorKind::CyclicInclude` - An included content includes itself, only
+    ///     with a resolver (see `Model::load_with_resolver`).
+    /// * `ReadError::Io` - An IO error occurred while reading.
+    /// 
+    /// ```
+    /// use svggen::{Model, ParseErrorKind};
+    /// 
+    /// let error = "#IF 0\n<text/>\n".parse::<Model>().unwrap_err();
+    /// assert_eq!((error.line(), error.kind()), (1, ParseErrorKind::UnclosedBlock));
+    /// 
+    /// let error = "<text/>\n#END\n".parse::<Model>().unwrap_err();
+    /// assert_eq!((error.line(), error.kind()), (2, ParseErrorKind::UnexpectedEnd));
+    /// 
+    /// let error = "#FOR 0\n#END\n<text>#ITEM</text>\n".parse::<Model>().unwrap_err();
+    /// assert_eq!((error.line(), error.kind()), (3, ParseErrorKind::UnexpectedItem));
+    /// ```
+    fn load<R: std::io::Read>(reader: &mut R) -> Result<Self, ReadError<Self::ParseError>> {
+        ModelParser::new().parse(reader)
+    }
+}
+
+impl FromStr for Model {
+    type Err = ParseError;
+
+    /// Creates a new model from a string.
+    /// 
+    /// The string is parsed by `Model::load`, which is the only model parser, so
+    /// both entry points always give the same parts (final line terminator included).
+    /// 
+    /// # Arguments
+    /// 
+    /// * `s` - The string to parse the model from.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, ModelPart};
+    /// 
+    /// let model: Model = "<svg>\n    <text>#GET 0</text>\n</svg>".parse().unwrap();
+    /// 
+    /// assert_eq!(model.parts(), &[
+    ///     ModelPart::Text(b"<svg>\n    <text>".to_vec().into()),
+    ///     ModelPart::Argument(0),
+    ///     ModelPart::Text(b"</text>\n</svg>".to_vec().into()),
+    /// ]);
+    /// ```
+    /// 
+    /// ```
+    /// # #[cfg(feature = "std")] {
+    /// use rutil::read::Readable;
+    /// use svggen::Model;
+    /// 
+    /// let source = "<svg>\n#GET 0\n<text>#GET 1</text>\n</svg>\n";
+    /// 
+    /// let parsed: Model = source.parse().unwrap();
+    /// let loaded = Model::load(&mut source.as_bytes()).unwrap();
+    /// assert_eq!(parsed, loaded);
+    /// # }
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parser = ModelParser::new().capacity(Model::estimate_parts_capacity(s.len()));
+        parser.parse_bytes(s.as_bytes())
+    }
+}
+
+impl TryFrom<&[u8]> for Model {
+    type Error = ParseError;
+
+    /// Creates a new model from bytes.
+    /// 
+    /// The bytes are parsed by `Model::load`, so they do not need to be valid UTF-8.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `bytes` - The bytes to parse the model from.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, ModelPart, ParseErrorKind};
+    /// 
+    /// let model = Model::try_from(b"<text>#GET 0</text>\xff".as_slice()).unwrap();
+    /// 
+    /// assert_eq!(model.parts(), &[
+    ///     ModelPart::Text(b"<text>".to_vec().into()),
+    ///     ModelPart::Argument(0),
+    ///     ModelPart::Text(b"</text>\xff".to_vec().into()),
+    /// ]);
+    /// 
+    /// let error = Model::try_from(b"<svg>\n#END\n".as_slice()).unwrap_err();
+    /// assert_eq!(error.line(), 2);
+    /// assert_eq!(error.kind(), ParseErrorKind::UnexpectedEnd);
+    /// ```
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        let parser = ModelParser::new().capacity(Model::estimate_parts_capacity(bytes.len()));
+        parser.parse_bytes(bytes)
+    }
+}
+
+impl TryFrom<Vec<u8>> for Model {
+    type Error = ParseError;
+
+    /// Creates a new model from bytes.
+    /// 
+    /// See `TryFrom<&[u8]>` for more details.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `bytes` - The bytes to parse the model from.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, Argument};
+    /// 
+    /// let model = Model::try_from(b"<text>#GET 0</text>".to_vec()).unwrap();
+    /// let image = model.generate(&[Argument::from("Hello".as_bytes())]).unwrap();
+    /// assert_eq!(image.content(), b"<text>Hello</text>");
+    /// ```
+    fn try_from(bytes: Vec<u8>) -> Result<Self, Self::Error> {
+        Model::try_from(bytes.as_slice())
+    }
+}
+
+impl TryFrom<Box<[u8]>> for Model {
+    type Error = ParseError;
+
+    /// Creates a new model from bytes.
+    /// 
+    /// See `TryFrom<&[u8]>` for more details.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `bytes` - The bytes to parse the model from.
+    fn try_from(bytes: Box<[u8]>) -> Result<Self, Self::Error> {
+        Model::try_from(&*bytes)
+    }
+}
+
+// ========================= //
+// ====== MAPPED MODEL ===== //
+// ========================= //
+
+/// A part of a `MappedModel`.
+#[cfg(feature = "memmap2")]
+#[derive(Debug)]
+enum MappedPart {
+    /// A text read from the mapped file.
+    Mapped(Range<usize>),
+
+    /// A part that is neither a text nor a block.
+    Part(ModelPart),
+
+    /// A conditional block (see `ModelPart::Conditional`).
+    Conditional {
+        /// The index of the argument of the block.
+        index: usize,
+
+        /// The parts of the block.
+        body: Box<[MappedPart]>,
+    },
+
+    /// A repetition block (see `ModelPart::Repetition`).
+    Repetition {
+        /// The index of the argument of the block.
+        index: usize,
+
+        /// The parts of the block.
+        body: Box<[MappedPart]>,
+    },
+}
+
+#[cfg(feature = "memmap2")]
+impl ParsedPart for MappedPart {
+    /// The positions of the text in the file.
+    type Text = Vec<Range<usize>>;
+
+    fn push_text(text: &mut Vec<Range<usize>>, slice: &[u8], offset: usize) {
+        match text.last_mut() {
+            _ if slice.is_empty() => (),
+            Some(last) if last.end == offset => last.end += slice.len(),
+            _ => text.push(offset..offset + slice.len()),
+        }
+    }
+
+    fn flush_text(text: &mut Vec<Range<usize>>, parts: &mut Vec<Self>) {
+        parts.extend(text.drain(..).map(MappedPart::Mapped));
+    }
+
+    fn part(part: ModelPart) -> Self {
+        MappedPart::Part(part)
+    }
+
+    fn conditional(index: usize, body: Box<[Self]>) -> Self {
+        MappedPart::Conditional { index, body }
+    }
+
+    fn repetition(index: usize, body: Box<[Self]>) -> Self {
+        MappedPart::Repetition { index, body }
+    }
+
+    fn with_defaults(parts: Box<[Self]>, defaults: &Map<usize, Box<[u8]>>) -> Box<[Self]> {
+        parts.into_vec().into_iter().map(|part| match part {
+            MappedPart::Part(ModelPart::Argument(index)) => match defaults.get(&index) {
+                Some(default) => MappedPart::Part(ModelPart::ArgumentOr { index, default: default.clone() }),
+                None => MappedPart::Part(ModelPart::Argument(index)),
+            },
+            MappedPart::Conditional { index, body } => MappedPart::Conditional { index, body: Self::with_defaults(body, defaults) },
+            MappedPart::Repetition { index, body } => MappedPart::Repetition { index, body: Self::with_defaults(body, defaults) },
+            part => part,
+        }).collect()
+    }
+}
+
+/// Adds the index of the argument of each variable set by the parts, including inside blocks.
+#[cfg(feature = "memmap2")]
+fn mapped_variables(parts: &[MappedPart], variables: &mut Map<Box<str>, usize>) {
+    for part in parts {
+        match part {
+            MappedPart::Part(ModelPart::SetVar { name, index }) => {
+                variables.insert(name.clone(), *index);
+            }
+            MappedPart::Conditional { body, .. } | MappedPart::Repetition { body, .. } => mapped_variables(body, variables),
+            _ => (),
+        }
+    }
+}
+
+/// Writes the parts of a `MappedModel` to a writer, as `write_parts` does.
+#[cfg(feature = "memmap2")]
+fn write_mapped_parts<W: io::Write>(map: &[u8], parts: &[MappedPart], writer: &mut W, context: Context) -> Result<(), GenerateError> {
+    for part in parts {
+        match part {
+            MappedPart::Mapped(range) => writer.write_all(&map[range.clone()])?,
+            MappedPart::Part(part) => write_parts(core::slice::from_ref(part), writer, context)?,
+            MappedPart::Conditional { index, body } => if is_present(context.args.get(*index)) {
+                write_mapped_parts(map, body, writer, context)?;
+            },
+            MappedPart::Repetition { index, body } => for item in items(context.args.get(*index)) {
+                write_mapped_parts(map, body, writer, context.with_item(item))?;
+            },
+        }
+    }
+    Ok(())
+}
+
+/// Copies the parts of a `MappedModel` out of the mapped file.
+#[cfg(feature = "memmap2")]
+fn copy_mapped_parts(map: &[u8], parts: &[MappedPart]) -> Box<[ModelPart]> {
+    let parts: Box<[ModelPart]> = parts.iter().map(|part| match part {
+        MappedPart::Mapped(range) => ModelPart::Text(map[range.clone()].into()),
+        MappedPart::Part(part) => part.clone(),
+        MappedPart::Conditional { index, body } => ModelPart::Conditional { index: *index, body: copy_mapped_parts(map, body) },
+        MappedPart::Repetition { index, body } => ModelPart::Repetition { index: *index, body: copy_mapped_parts(map, body) },
+    }).collect();
+    compile_parts(parts)
+}
+
+/// Returns the number of bytes of the texts of the parts, including inside blocks.
+#[cfg(feature = "memmap2")]
+fn mapped_parts_len(parts: &[MappedPart]) -> usize {
+    parts.iter().map(|part| match part {
+        MappedPart::Mapped(range) => range.len(),
+        MappedPart::Part(_) => 0,
+        MappedPart::Conditional { body, .. } | MappedPart::Repetition { body, .. } => mapped_parts_len(body),
+    }).sum()
+}
+
+/// A model whose texts are read from a memory-mapped file, created by `Model::load_mmap`.
+/// 
+/// It generates the same images as the `Model` loaded from the same file.
+/// The file must not be modified while the `MappedModel` exists (see `Model::load_mmap`).
+/// 
+/// Requires the `memmap2` feature.
+#[cfg(feature = "memmap2")]
+#[derive(Debug)]
+pub struct MappedModel {
+    /// The mapped file.
+    map: memmap2::Mmap,
+
+    /// The parts of the model.
+    parts: Box<[MappedPart]>,
+
+    /// The index of the argument of each variable.
+    variables: Map<Box<str>, usize>,
+}
+
+#[cfg(feature = "memmap2")]
+impl MappedModel {
+    /// Creates an image from the model (see `Model::generate`).
+    /// 
+    /// # Arguments
+    /// 
+    /// * `args` - The arguments to use.
+    /// 
+    /// # Errors
+    /// 
+    /// * `GenerateError::MissingArgument` - An argument referenced by the model was not given.
+    /// * `GenerateError::DepthExceeded` - The nested models are deeper than `Model::DEFAULT_MAX_DEPTH`.
+    pub fn generate(&self, args: &[Argument]) -> Result<Image, GenerateError> {
+        let mut content = Vec::with_capacity(self.map.len());
+        self.write(&mut content, args)?;
+        Ok(Image { content: content.into() })
+    }
+
+    /// Write the model to a writer (see `Model::write`).
+    /// 
+    /// # Arguments
+    /// 
+    /// * `writer` - The writer to write the model to.
+    /// * `args` - The arguments to use.
+    /// 
+    /// # Errors
+    /// 
+    /// * `GenerateError::MissingArgument` - An argument referenced by the model was not given.
+    /// * `GenerateError::DepthExceeded` - The nested models are deeper than `Model::DEFAULT_MAX_DEPTH`.
+    /// * `GenerateError::Io` - An IO error occurred while writing.
+    pub fn write<W: io::Write>(&self, writer: &mut W, args: &[Argument]) -> Result<(), GenerateError> {
+        let named = Map::new();
+        let context = Context::new(args, &named, &self.variables, Model::DEFAULT_MAX_DEPTH);
+        write_mapped_parts(&self.map, &self.parts, writer, context)
+    }
+
+    /// Returns the number of bytes of text read from the mapped file, including inside the blocks.
+    pub fn mapped_len(&self) -> usize {
+        mapped_parts_len(&self.parts)
+    }
+
+    /// Copies the model out of the mapped file.
+    pub fn to_model(&self) -> Model {
+        Model::from(copy_mapped_parts(&self.map, &self.parts))
+    }
+}
+
+/// An image whose content is read from a memory-mapped file, created by `Image::load_mmap`.
+/// 
+/// It can be given as an argument without copying its content, with `Argument::Lazy`.
+/// The file must not be modified while the `MappedImage` exists (see `Image::load_mmap`).
+/// 
+/// Requires the `memmap2` feature.
+#[cfg(feature = "memmap2")]
+#[derive(Debug)]
+pub struct MappedImage {
+    /// The mapped file.
+    map: memmap2::Mmap,
+}
+
+#[cfg(feature = "memmap2")]
+impl MappedImage {
+    /// Returns the content of the image.
+    pub fn content(&self) -> &[u8] {
+        &self.map
+    }
+
+    /// Copies the image out of the mapped file.
+    pub fn to_image(&self) -> Image {
+        Image::from(&self.map[..])
+    }
+}
+
+#[cfg(feature = "memmap2")]
+impl LazyContent for MappedImage {
+    /// Returns the content of the image, read from the mapped file.
+    fn content(&self) -> &[u8] {
+        &self.map
+    }
+}
+
+// ========================= //
+// ===== COMPILED MODEL ==== //
+// ========================= //
+
+/// A model prepared for fast generation, created by `Model::precompiled`.
+/// 
+/// All the top-level texts are joined in a single buffer, and the other parts
+/// are insertion points in this buffer. Generating an image copies the text up
+/// to the next insertion point, writes the part, and so on.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use svggen::{Model, Argument};
+/// 
+/// let model: Model = "<svg>\n#IF 1\n<title>#GET 1</title>\n#END\n<text>#GET 0</text>\n</svg>".parse().unwrap();
+/// let compiled = model.precompiled();
+/// 
+/// for args in [
+///     vec![Argument::from("Hello")],
+///     vec![Argument::from("Hello"), Argument::from("Greeting")],
+///     vec![Argument::from("Hello"), Argument::Empty],
+/// ] {
+///     assert_eq!(compiled.generate(&args).unwrap(), model.generate(&args).unwrap());
+/// }
+/// assert_eq!(compiled.generate(&[]).unwrap_err().index(), Some(0));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompiledModel {
+    /// The top-level texts of the model, joined.
+    text: Box<[u8]>,
+
+    /// The other top-level parts, with the position in `text` where each one is written.
+    insertions: Box<[(usize, ModelPart)]>,
+
+    /// The index of the argument of each variable.
+    variables: Map<Box<str>, usize>,
+}
+
+impl CompiledModel {
+    /// Creates an image from the model (see `Model::generate`).
+    /// 
+    /// # Arguments
+    /// 
+    /// * `args` - The arguments to use.
+    /// 
+    /// # Errors
+    /// 
+    /// * `GenerateError::MissingArgument` - An argument referenced by the model was not given.
+    /// * `GenerateError::DepthExceeded` - The nested models are deeper than `Model::DEFAULT_MAX_DEPTH`.
+    pub fn generate(&self, args: &[Argument]) -> Result<Image, GenerateError> {
+        let mut content = Vec::with_capacity(self.text.len());
+        self.write_output(&mut content, args)?;
+        Ok(Image { content: content.into() })
+    }
+
+    /// Write the model to a writer (see `Model::write`).
+    /// 
+    /// Requires the `std` feature.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `writer` - The writer to write the model to.
+    /// * `args` - The arguments to use.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{Model, Argument};
+    /// 
+    /// let model: Model = "<text>#GET 0</text>".parse().unwrap();
+    /// let mut buffer: Vec<u8> = Vec::new();
+    /// model.precompiled().write(&mut buffer, &[Argument::from("Hi")]).unwrap();
+    /// 
+    /// assert_eq!(buffer, b"<text>Hi</text>");
+    /// ```
+    /// 
+    /// # Errors
+    /// 
+    /// * `GenerateError::MissingArgument` - An argument referenced by the model was not given.
+    /// * `GenerateError::DepthExceeded` - The nested models are deeper than `Model::DEFAULT_MAX_DEPTH`.
+    /// * `GenerateError::Io` - An IO error occurred while writing.
+    #[cfg(feature = "std")]
+    pub fn write<W: io::Write>(&self, writer: &mut W, args: &[Argument]) -> Result<(), GenerateError> {
+        self.write_output(writer, args)
+    }
+
+    /// Writes the model to an output (see `CompiledModel::write`).
+    fn write_output<W: Output + ?Sized>(&self, writer: &mut W, args: &[Argument]) -> Result<(), GenerateError> {
+        let named = Map::new();
+        let context = Context::new(args, &named, &self.variables, Model::DEFAULT_MAX_DEPTH);
+        let mut position = 0;
+        for (offset, part) in self.insertions.iter() {
+            writer.write_bytes(&self.text[position..*offset])?;
+            write_parts(core::slice::from_ref(part), writer, context)?;
+            position = *offset;
+        }
+        writer.write_bytes(&self.text[position..])?;
+        Ok(())
+    }
+}
+
+impl From<&Model> for CompiledModel {
+    /// Prepares a model for fast generation (see `Model::precompiled`).
+    fn from(model: &Model) -> Self {
+        let mut text: Vec<u8> = Vec::new();
+        let mut insertions: Vec<(usize, ModelPart)> = Vec::new();
+        for part in model.parts.iter() {
+            match part {
+                ModelPart::Text(content) => text.extend_from_slice(content),
+                part => insertions.push((text.len(), part.clone())),
+            }
+        }
+        CompiledModel { text: text.into(), insertions: insertions.into(), variables: model.cached_variables().clone() }
+    }
+}
+
+// ========================= //
+// ====== MODEL PARSER ===== //
+// ========================= //
+
+/// A parser for models, with a configurable directive syntax.
+/// 
+/// By default, directives are written `#GET 0`, `#IF 0`, `#END`... and
+/// `Model::load` is a shortcut for `ModelParser::new().parse(reader)`. The
+/// prefix (`#`) and an optional suffix can be changed, for example to write
+/// `{{GET 0}}` in models where `#` is common. A directive preceded by the
+/// prefix once more is escaped, like `##GET` with the default prefix.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use svggen::{ModelParser, Argument};
+/// 
+/// let default = ModelParser::new();
+/// let braces = ModelParser::new().prefix("{{").suffix("}}");
+/// 
+/// let model = default.parse_bytes("<text fill=\"#f00\">#GET 0</text>".as_bytes()).unwrap();
+/// let braced = braces.parse_bytes("<text fill=\"#f00\">{{GET 0}}</text>".as_bytes()).unwrap();
+/// assert_eq!(model, braced);
+/// 
+/// let image = braced.generate(&[Argument::from("Hello".as_bytes())]).unwrap();
+/// assert_eq!(image.content(), b"<text fill=\"#f00\">Hello</text>");
+/// ```
+/// 
+/// Every directive uses the configured syntax, and the default one is kept as text:
+/// 
+/// ```
+/// use svggen::{ModelParser, ModelPart, Argument};
+/// 
+/// let parser = ModelParser::new().prefix("{{").suffix("}}");
+/// 
+/// let source = "{{REM A legend}}\n{{FOR 0}}\n<text>{{ITEM}} {{GET 1|px|}}</text>\n{{END}}\n#GET 0 {{{{GET 0}}\n";
+/// let model = parser.parse_bytes(source.as_bytes()).unwrap();
+/// 
+/// assert_eq!(model.parts(), &[
+///     ModelPart::Repetition {
+///         index: 0,
+///         body: vec![
+///             ModelPart::Text(b"<text>".to_vec().into()),
+///             ModelPart::Item,
+///             ModelPart::Text(b" ".to_vec().into()),
+///             ModelPart::ArgumentOr { index: 1, default: b"px".to_vec().into() },
+///             ModelPart::Text(b"</text>\n".to_vec().into()),
+///         ].into(),
+///     },
+///     ModelPart::Text(b"#GET 0 {{GET 0}}\n".to_vec().into()),
+/// ]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelParser {
+    /// The text starting every directive.
+    prefix: Box<[u8]>,
+
+    /// The text ending every directive (except comments).
+    suffix: Box<[u8]>,
+
+    /// The number of parts allocated before parsing.
+    capacity: usize,
+
+    /// Whether the keywords of the directives are matched ignoring the ASCII case.
+    case_insensitive: bool,
+}
+
+impl ModelParser {
+    /// Creates a parser with the default syntax (`#GET 0`).
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// # #[cfg(feature = "std")] {
+    /// use rutil::read::Readable;
+    /// use svggen::{Model, ModelParser};
+    /// 
+    /// let source = "<svg>\n#GET 0\n</svg>";
+    /// let model = ModelParser::new().parse(&mut source.as_bytes()).unwrap();
+    /// assert_eq!(model, Model::load(&mut source.as_bytes()).unwrap());
+    /// # }
+    /// ```
+    pub fn new() -> Self {
+        ModelParser { prefix: b"#".to_vec().into(), suffix: Box::default(), capacity: 0, case_insensitive: false }
+    }
+
+    /// Sets the text starting every directive (`#` by default).
+    /// 
+    /// # Arguments
+    /// 
+    /// * `prefix` - The text starting every directive.
+    /// 
+    /// # Panics
+    /// 
+    /// Panics if the prefix is empty.
+    pub fn prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.as_bytes().into();
+        assert!(!self.prefix.is_empty(), "the directive prefix cannot be empty");
+        self
+    }
+
+    /// Sets the text ending every directive (nothing by default).
+    /// 
+    /// Comments only need to start with the prefix, so they do not need the suffix.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `suffix` - The text ending every directive.
+    pub fn suffix(mut self, suffix: &str) -> Self {
+        self.suffix = suffix.as_bytes().into();
+        self
+    }
+
+    /// Sets the number of parts allocated before parsing (none by default).
+    /// 
+    /// The parts grow as needed, so this is only an optimization. When the size
+    /// of the source is known, `Model::estimate_parts_capacity` gives a good value.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `capacity` - The number of parts allocated before parsing.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Sets whether the keywords of the directives ignore the ASCII case (strict by default).
+    /// 
+    /// When enabled, `#get 0`, `#Get 0` and `#GET 0` are the same reference, and
+    /// the same goes for every other keyword (`#if`, `#For`, `#item`, `#rem`...).
+    /// Only the keyword is concerned: the prefix and the suffix are still matched
+    /// exactly, so with a `{{` prefix `{{get 0}}` is a reference. Whitespace at the
+    /// end of a directive line is always ignored.
+    /// 
+    /// The escaped keywords are case-insensitive too (`##get` is kept as `#get`).
+    /// 
+    /// # Arguments
+    /// 
+    /// * `enabled` - Whether the keywords ignore the ASCII case.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{ModelParser, ModelPart};
+    /// 
+    /// let parser = ModelParser::new().case_insensitive(true);
+    /// for source in ["#get 0", "#Get 0", "#GET 0"] {
+    ///     let model = parser.parse_bytes(source.as_bytes()).unwrap();
+    ///     assert_eq!(model.parts(), &[ModelPart::Argument(0)]);
+    /// }
+    /// 
+    /// let model = parser.parse_bytes("#if 0 \n<g/>\n#End\n".as_bytes()).unwrap();
+    /// assert!(matches!(model.parts(), [ModelPart::Conditional { index: 0, .. }]));
+    /// 
+    /// let model = parser.parse_bytes("##get 0".as_bytes()).unwrap();
+    /// assert_eq!(model.parts(), &[ModelPart::from("#get 0".as_bytes())]);
+    /// 
+    /// // The prefix and the suffix are still matched exactly
+    /// let parser = ModelParser::new().prefix("{{").suffix("}}").case_insensitive(true);
+    /// let model = parser.parse_bytes("{{get 0}} {{ GET 0}}".as_bytes()).unwrap();
+    /// assert_eq!(model.parts(), &[ModelPart::Argument(0), ModelPart::from(" {{ GET 0}}".as_bytes())]);
+    /// 
+    /// // The default parser is strict
+    /// let model = ModelParser::new().parse_bytes("#get 0".as_bytes()).unwrap();
+    /// assert_eq!(model.parts(), &[ModelPart::from("#get 0".as_bytes())]);
+    /// ```
+    pub fn case_insensitive(mut self, enabled: bool) -> Self {
+        self.case_insensitive = enabled;
+        self
+    }
+
+    /// Creates a new model from a reader.
+    /// 
+    /// See `Model::load` for the syntax of a model, written here with the
+    /// configured prefix and suffix.
+    /// 
+    /// Requires the `std` feature.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `reader` - The reader to read the model from.
+    /// 
+    /// # Errors
+    /// 
+    /// * `ReadError::Parse(ParseError)` - The model is invalid (see `ParseErrorKind`).
+    /// * `ReadError::Io` - An IO error occurred while reading.
+    #[cfg(feature = "std")]
+    pub fn parse<R: io::Read>(&self, reader: &mut R) -> Result<Model, ReadError<ParseError>> {
+        self.parse_including(reader, None)
+    }
+
+    /// Creates a new model from bytes.
+    /// 
+    /// This is `ModelParser::parse` without a reader, available without the `std` feature.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `source` - The bytes to parse the model from.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use svggen::{ModelParser, ModelPart};
+    /// 
+    /// let parser = ModelParser::new().prefix("{{").suffix("}}");
+    /// let model = parser.parse_bytes(b"<text>{{GET 0}}</text>").unwrap();
+    /// 
+    /// assert_eq!(model.parts(), &[
+    ///     ModelPart::from("<text>".as_bytes()),
+    ///     ModelPart::Argument(0),
+    ///     ModelPart::from("</text>".as_bytes()),
+    /// ]);
+    /// ```
+    /// 
+    /// # Errors
+    /// 
+    /// * `ParseError` - The model is invalid (see `ParseErrorKind`).
+    pub fn parse_bytes(&self, source: &[u8]) -> Result<Model, ParseError> {
+        match self.parse_parts::<ModelPart, core::convert::Infallible>(source, None) {
+            Ok(parts) => Ok(Model::from(parts)),
+            Err(SourceError::Parse(err)) => Err(err),
+            Err(SourceError::Resolver(never)) => match never {},
+        }
+    }
+
+    /// Creates a new model from a reader, expanding the `#INCLUDE` directives.
+    /// 
+    /// See `Model::load_with_resolver` for the syntax of an include, written here
+    /// with the configured prefix and suffix.
+    /// 
+    /// Requires the `std` feature.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `reader` - The reader to read the model from.
+    /// * `resolver` - The function giving the content included for each name.
+    /// 
+    /// # Errors
+    /// 
+    /// * `ReadError::Parse(ParseError)` - The model is invalid (see `ParseErrorKind`), or some
+    ///   content includes itself (`ParseErrorKind::CyclicInclude`).
+    /// * `ReadError::Io` - An IO error occurred while reading, or returned by the resolver.
+    #[cfg(feature = "std")]
+    pub fn parse_with_resolver<R, F>(&self, reader: &mut R, resolver: F) -> Result<Model, ReadError<ParseError>>
+    where
+        R: io::Read,
+        F: Fn(&str) -> io::Result<Vec<u8>>,
+    {
+        self.parse_including(reader, Some(&resolver))
+    }
+
+    /// Creates a new model from a reader, expanding the `#INCLUDE` directives if there is a resolver.
+    /// 
+    /// Without a resolver, an `#INCLUDE` line is kept as text.
+    #[cfg(feature = "std")]
+    fn parse_including<R: io::Read>(
+        &self,
+        reader: &mut R,
+        resolver: Option<Resolver<io::Error>>,
+    ) -> Result<Model, ReadError<ParseError>> {
+        let mut source = Vec::new();
+        reader.read_to_end(&mut source)?;
+        Ok(Model::from(self.parse_parts::<ModelPart, _>(&source, resolver)?))
+    }
+
+    /// Parses the parts of a model from bytes, expanding the `#INCLUDE` directives if there is a resolver.
+    /// 
+    /// The texts are built by `P` from the slices of the lines and their position
+    /// in the source (the positions in the included contents are meaningless).
+    fn parse_parts<P: ParsedPart, E>(
+        &self,
+        source: &[u8],
+        resolver: Option<Resolver<E>>,
+    ) -> Result<Box<[P]>, SourceError<E>> {
+        let mut buffer = P::Text::default();
+        let mut parts: Vec<P> = Vec::with_capacity(self.capacity);
+
+        // The directive, the parent parts and the line of each open block
+        let mut blocks: Vec<(Directive, Vec<P>, usize)> = Vec::new();
+
+        // The variables set by the model, and the first line using each variable
+        let mut variables: Set<Box<str>> = Set::new();
+        let mut uses: Map<Box<str>, usize> = Map::new();
+        
+        // The index of the next bare `#GET`
+        let mut next_index = 0;
+
+        // The default text of the arguments with a `#DEFAULT`
+        let mut defaults: Map<usize, Box<[u8]>> = Map::new();
+
+        // The name, the content and the read length of each include being read, the innermost last
+        let mut includes: Vec<(Box<str>, Vec<u8>, usize)> = Vec::new();
+        
+        // For each line (keeping its original line terminator and any non-UTF-8 byte)
+        let mut number = 0;
+        let mut position = 0;
+        loop {
+            let mut offset = position;
+            let mut line = if let Some((_, content, read)) = includes.last_mut() {
+                // The lines of an included content have the number of the `#INCLUDE` line
+                if *read == content.len() {
+                    includes.pop();
+                    continue;
+                }
+                let line = first_line(&content[*read..]);
+                *read += line.len();
+                line
+            } else if position == source.len() {
+                break;
+            } else {
+                let line = first_line(&source[position..]);
+                number += 1;
+                position += line.len();
+                line
+            };
+            let error = |kind| SourceError::Parse(ParseError { line: number, kind });
+
+            // Handle the directive lines
+            let trimmed = line.trim_ascii();
+            match self.parse_directive(trimmed) {
+                // Drop the comment with its line terminator, without splitting the text
+                Some(Directive::Comment) => continue,
+                Some(Directive::Set { name, index }) => {
+                    if !variables.insert(name.clone()) {
+                        return Err(error(ParseErrorKind::DuplicateVariable));
+                    }
+
+                    // Add the text before the directive to the parts (if it's not empty)
+                    P::flush_text(&mut buffer, &mut parts);
+                    parts.push(P::part(ModelPart::SetVar { name, index }));
+                    continue;
+                }
+                Some(Directive::Default { index, text }) => {
+                    if text.contains(&b'|') {
+                        return Err(error(ParseErrorKind::InvalidDefault));
+                    }
+                    if defaults.insert(index, text).is_some() {
+                        return Err(error(ParseErrorKind::DuplicateDefault));
+                    }
+                    continue;
+                }
+                Some(Directive::GetLine(index)) => {
+                    // Add the text before the directive to the parts (if it's not empty)
+                    P::flush_text(&mut buffer, &mut parts);
+                    parts.push(P::part(ModelPart::ArgumentLine(index)));
+                    continue;
+                }
+                Some(Directive::Include(name)) => if let Some(resolver) = resolver {
+                    if includes.iter().any(|(included, _, _)| *included == name) {
+                        return Err(error(ParseErrorKind::CyclicInclude));
+                    }
+
+                    // Read the lines of the included content before the next lines
+                    let content = resolver(&name).map_err(SourceError::Resolver)?;
+                    includes.push((name, content, 0));
+                    continue;
+                },
+                Some(directive) => {
+                    // Add the text before the directive to the parts (if it's not empty)
+                    P::flush_text(&mut buffer, &mut parts);
+
+                    if let Directive::End = directive {
+                        let (directive, parent, _) = blocks.pop().ok_or_else(|| error(ParseErrorKind::UnexpectedEnd))?;
+                        let body = core::mem::replace(&mut parts, parent).into();
+                        parts.push(match directive {
+                            Directive::If(index) => P::conditional(index, body),
+                            Directive::For(index) => P::repetition(index, body),
+                            Directive::End
+                            | Directive::Comment
+                            | Directive::Set { .. }
+                            | Directive::Include(_)
+                            | Directive::Default { .. }
+                            | Directive::GetLine(_) => {
+                                unreachable!("only `#IF` and `#FOR` open a block")
+                            }
+                        });
+                    } else {
+                        blocks.push((directive, core::mem::take(&mut parts), number));
+                    }
+                    continue;
+                }
+                None if self.parse_escaped_directive(trimmed).is_some() => {
+                    // Remove the prefix escaping the directive
+                    let indent = line.len() - line.trim_ascii_start().len();
+                    P::push_text(&mut buffer, &line[..indent], offset);
+                    line = &line[indent + self.prefix.len()..];
+                    offset += indent + self.prefix.len();
+                }
+                None => (),
+            }
+
+            // Search for argument references in the line
+            let mut start = 0;
+            let mut i = 0;
+            while i < line.len() {
+                if let Some(len) = self.escaped_reference_len(&line[i..]) {
+                    // Collapse the escaped reference into a literal reference
+                    P::push_text(&mut buffer, &line[start..i], offset + start);
+                    i += self.prefix.len();
+                    start = i;
+                    i += len;
+                } else if let Some(len) = self.parse_item(&line[i..]) {
+                    if !blocks.iter().any(|(directive, _, _)| matches!(directive, Directive::For(_))) {
+                        return Err(error(ParseErrorKind::UnexpectedItem));
+                    }
+
+                    // Add the text before the item to the parts (if it's not empty)
+                    P::push_text(&mut buffer, &line[start..i], offset + start);
+                    P::flush_text(&mut buffer, &mut parts);
+                    parts.push(P::part(ModelPart::Item));
+                    i += len;
+                    start = i;
+                } else if let Some((name, len)) = self.parse_variable(&line[i..]) {
+                    // Add the text before the variable to the parts (if it's not empty)
+                    P::push_text(&mut buffer, &line[start..i], offset + start);
+                    P::flush_text(&mut buffer, &mut parts);
+
+                    // The variable can be set later in the model
+                    uses.entry(name.clone()).or_insert(number);
+                    parts.push(P::part(ModelPart::GetVar(name)));
+                    i += len;
+                    start = i;
+                } else if let Some((part, len)) = self.parse_argument(&line[i..], &mut next_index) {
+                    // Add the text before the reference to the parts (if it's not empty)
+                    P::push_text(&mut buffer, &line[start..i], offset + start);
+                    P::flush_text(&mut buffer, &mut parts);
 
-            // Add the line to the text buffer
-            buffer.append(&mut line.into_bytes());
+                    // Add the argument reference to the parts
+                    parts.push(P::part(part));
+                    i += len;
+                    start = i;
+                } else {
+                    i += 1;
+                }
+            }
+
+            // Add the rest of the line to the text buffer
+            P::push_text(&mut buffer, &line[start..], offset + start);
         }
 
         // Add the text buffer to the parts (if it's not empty)
-        if buffer.len() > 0 {
-            parts.push(ModelPart::Text(buffer.into()));
+        P::flush_text(&mut buffer, &mut parts);
+        if let Some((_, _, line)) = blocks.last() {
+            return Err(SourceError::Parse(ParseError { line: *line, kind: ParseErrorKind::UnclosedBlock }));
+        }
+        if let Some(line) = uses.iter().filter(|(name, _)| !variables.contains(*name)).map(|(_, line)| *line).min() {
+            return Err(SourceError::Parse(ParseError { line, kind: ParseErrorKind::UndefinedVariable }));
         }
         
-        // Return the model
-        Ok(Model { parts: parts.into() })
+        // Return the parts of the model
+        if defaults.is_empty() {
+            return Ok(parts.into());
+        }
+        Ok(P::with_defaults(parts.into(), &defaults))
+    }
+
+    /// Returns the rest of the text if it starts with the prefix and the keyword.
+    /// 
+    /// The keyword ignores the ASCII case if the parser is case-insensitive.
+    fn strip_keyword<'t>(&self, text: &'t [u8], keyword: &[u8]) -> Option<&'t [u8]> {
+        let text = text.strip_prefix(&*self.prefix)?;
+        let head = text.get(..keyword.len())?;
+        let matched = if self.case_insensitive { head.eq_ignore_ascii_case(keyword) } else { head == keyword };
+        matched.then(|| &text[keyword.len()..])
+    }
+
+    /// Parses a directive from the trimmed content of a line.
+    /// 
+    /// Except for comments, the whole line must be the directive, so `#IF 0 <text/>`
+    /// is kept as text.
+    fn parse_directive(&self, line: &[u8]) -> Option<Directive> {
+        if self.strip_keyword(line, b"//").is_some()
+            || self.strip_keyword(line, b"REM").is_some_and(|rest| rest.first().is_none_or(u8::is_ascii_whitespace))
+        {
+            return Some(Directive::Comment);
+        }
+        let line = line.strip_suffix(&*self.suffix)?;
+        if self.strip_keyword(line, b"END") == Some(b"") {
+            return Some(Directive::End);
+        }
+        if let Some(rest) = self.strip_keyword(line, b"SET") {
+            return self.parse_set(rest);
+        }
+        if let Some(rest) = self.strip_keyword(line, b"DEFAULT") {
+            return self.parse_default(rest);
+        }
+        if let Some(rest) = self.strip_keyword(line, b"INCLUDE") {
+            let name = rest.trim_ascii_start();
+            if name.len() == rest.len() || name.is_empty() {
+                return None;
+            }
+            return Some(Directive::Include(core::str::from_utf8(name).ok()?.into()));
+        }
+        let (rest, directive): (_, fn(usize) -> Directive) = if let Some(rest) = self.strip_keyword(line, b"IF") {
+            (rest, Directive::If)
+        } else if let Some(rest) = self.strip_keyword(line, b"GETLINE") {
+            (rest, Directive::GetLine)
+        } else {
+            (self.strip_keyword(line, b"FOR")?, Directive::For)
+        };
+        let digits = rest.trim_ascii_start();
+        if digits.len() == rest.len() || digits.is_empty() || !digits.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        Some(directive(parse_index(digits)?))
+    }
+
+    /// Parses the rest of a `#DEFAULT n text` directive, after the keyword.
+    /// 
+    /// The text is everything after the whitespace following the index, and can be empty.
+    fn parse_default(&self, rest: &[u8]) -> Option<Directive> {
+        let digits = rest.trim_ascii_start();
+        let len = digits.iter().take_while(|c| c.is_ascii_digit()).count();
+        if digits.len() == rest.len() || len == 0 {
+            return None;
+        }
+        let text = &digits[len..];
+        let trimmed = text.trim_ascii_start();
+        if !text.is_empty() && trimmed.len() == text.len() {
+            return None;
+        }
+        let index = parse_index(&digits[..len])?;
+        Some(Directive::Default { index, text: trimmed.into() })
+    }
+
+    /// Parses the rest of a `#SET name = n` directive, after the keyword.
+    fn parse_set(&self, rest: &[u8]) -> Option<Directive> {
+        let name = rest.trim_ascii_start();
+        let len = name_len(name);
+        if name.len() == rest.len() || len == 0 {
+            return None;
+        }
+        let digits = name[len..].trim_ascii_start().strip_prefix(b"=")?.trim_ascii_start();
+        if digits.is_empty() || !digits.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        let index = parse_index(digits)?;
+        Some(Directive::Set { name: core::str::from_utf8(&name[..len]).ok()?.into(), index })
+    }
+
+    /// Parses a directive preceded by any number of prefixes (`#IF 0`, `##IF 0`,
+    /// `###END`...) from the trimmed content of a line.
+    /// 
+    /// When parsing, a line that is not a directive but matches this is an escaped
+    /// directive, and its first prefix is removed.
+    fn parse_escaped_directive(&self, mut line: &[u8]) -> Option<Directive> {
+        let mut escaped = line.strip_prefix(&*self.prefix)?;
+        while escaped.starts_with(&self.prefix) {
+            line = escaped;
+            escaped = &escaped[self.prefix.len()..];
+        }
+        self.parse_directive(line)
+    }
+
+    /// Returns the length of the escaped reference at the start of the given text,
+    /// without its first prefix (`#GET` for `##GET`, `#ITEM` for `##ITEM`).
+    fn escaped_reference_len(&self, text: &[u8]) -> Option<usize> {
+        let text = text.strip_prefix(&*self.prefix)?;
+        [&b"GET"[..], b"ITEM", b"VAR"].into_iter()
+            .find(|keyword| self.strip_keyword(text, keyword).is_some())
+            .map(|keyword| self.prefix.len() + keyword.len())
+    }
+
+    /// Parses an `#ITEM` reference at the start of the given text.
+    /// 
+    /// The reference must not be followed by a name character, so `#ITEMS` is kept as text.
+    /// 
+    /// Returns the length of the reference.
+    fn parse_item(&self, text: &[u8]) -> Option<usize> {
+        let rest = self.strip_keyword(text, b"ITEM")?;
+        if matches!(rest.first(), Some(c) if c.is_ascii_alphanumeric() || *c == b'_') {
+            return None;
+        }
+        rest.strip_prefix(&*self.suffix)?;
+        Some(self.prefix.len() + 4 + self.suffix.len())
+    }
+
+    /// Parses a `#VAR name` reference at the start of the given text.
+    /// 
+    /// Returns the name of the variable and the length of the reference.
+    fn parse_variable(&self, text: &[u8]) -> Option<(Box<str>, usize)> {
+        let rest = self.strip_keyword(text, b"VAR")?;
+        let spaces = rest.iter().take_while(|c| matches!(c, b' ' | b'\t')).count();
+        let len = name_len(&rest[spaces..]);
+        if spaces == 0 || len == 0 {
+            return None;
+        }
+        rest[spaces + len..].strip_prefix(&*self.suffix)?;
+        let name = core::str::from_utf8(&rest[spaces..spaces + len]).ok()?;
+        Some((name.into(), text.len() - rest.len() + spaces + len + self.suffix.len()))
+    }
+
+    /// Parses an argument reference at the start of the given text.
+    /// 
+    /// An argument reference is `#GET` followed by at least one space or tab and
+    /// either an index (digits) or a name (an ASCII letter or `_` followed by ASCII
+    /// letters, digits or `_`). The reference ends at the first other character.
+    /// 
+    /// A bare `#GET` (directly followed by anything but a space, a tab, a digit or a
+    /// name character) is the argument of index `next`, which is then incremented.
+    /// So `#GETLINE` and `#GET -1` are not references.
+    /// 
+    /// The reference can be directly followed by a default value between two `|`
+    /// (for example `#GET 0|Untitled|`). The default value is kept as is and cannot
+    /// contain a `|` or a line terminator. Without a closing `|`, the first `|` is
+    /// part of the text.
+    /// 
+    /// An index can instead be directly followed by a byte range (for example
+    /// `#GET 0[10..20]`), so only these bytes of the argument are written.
+    /// 
+    /// Returns the referenced argument and the length of the reference.
+    fn parse_argument(&self, text: &[u8], next: &mut usize) -> Option<(ModelPart, usize)> {
+        let (part, len) = self.parse_reference(text, *next)?;
+        let (part, end) = self.parse_reference_end(part, &text[len..])?;
+
+        // Only a bare reference has no space after the keyword
+        if len == self.prefix.len() + 3 {
+            *next += 1;
+        }
+        Some((part, len + end))
+    }
+
+    /// Parses the end of an argument reference (its byte range or default value,
+    /// and the suffix) at the start of the given text.
+    /// 
+    /// Returns the complete argument and the length of the end.
+    fn parse_reference_end(&self, part: ModelPart, rest: &[u8]) -> Option<(ModelPart, usize)> {
+        if let (ModelPart::Argument(index), Some((start, end, range))) = (&part, parse_range(rest)) {
+            if rest[range..].starts_with(&self.suffix) {
+                let part = ModelPart::ArgumentSlice { index: *index, start, end };
+                return Some((part, range + self.suffix.len()));
+            }
+        }
+        if rest.first() == Some(&b'|') {
+            if let Some(end) = rest[1..].iter().take_while(|c| !matches!(c, b'\n' | b'\r')).position(|c| *c == b'|') {
+                if rest[end + 2..].starts_with(&self.suffix) {
+                    let default = rest[1..1 + end].into();
+                    let part = match part {
+                        ModelPart::Argument(index) => ModelPart::ArgumentOr { index, default },
+                        ModelPart::NamedArgument(name) => ModelPart::NamedArgumentOr { name, default },
+                        part => part,
+                    };
+                    return Some((part, end + 2 + self.suffix.len()));
+                }
+            }
+        }
+        rest.strip_prefix(&*self.suffix)?;
+        Some((part, self.suffix.len()))
+    }
+
+    /// Parses an argument reference (without default value and suffix) at the start of the given text.
+    /// 
+    /// A bare reference is the argument of index `next`, and its length is the length of the keyword.
+    fn parse_reference(&self, text: &[u8], next: usize) -> Option<(ModelPart, usize)> {
+        let rest = self.strip_keyword(text, b"GET")?;
+        let keyword = text.len() - rest.len();
+        let spaces = rest.iter().take_while(|c| matches!(c, b' ' | b'\t')).count();
+        if spaces == 0 {
+            // A bare reference, unless the keyword is part of a word (such as `#GETLINE`)
+            if matches!(rest.first(), Some(c) if c.is_ascii_alphanumeric() || *c == b'_') {
+                return None;
+            }
+            return Some((ModelPart::Argument(next), keyword));
+        }
+
+        // Parse the index of the argument
+        let rest = &rest[spaces..];
+        let digits = rest.iter().take_while(|c| c.is_ascii_digit()).count();
+        if digits > 0 {
+            // The digits are ASCII, so only an overflow can make the parsing fail
+            let index = parse_index(&rest[..digits])?;
+            return Some((ModelPart::Argument(index), keyword + spaces + digits));
+        }
+
+        // Parse the name of the argument
+        let len = name_len(rest);
+        if len == 0 {
+            return None;
+        }
+        let name = core::str::from_utf8(&rest[..len]).ok()?;
+        Some((ModelPart::NamedArgument(name.into()), keyword + spaces + len))
+    }
+}
+
+/// A part created by `ModelParser`: a `ModelPart`, or a `MappedPart` (see `Model::load_mmap`).
+trait ParsedPart: Sized {
+    /// The text read before the next part.
+    type Text: Default;
+
+    /// Adds a slice of the source to the text, `offset` being its position in the source.
+    fn push_text(text: &mut Self::Text, slice: &[u8], offset: usize);
+
+    /// Adds the text to the parts (if it's not empty), leaving it empty.
+    fn flush_text(text: &mut Self::Text, parts: &mut Vec<Self>);
+
+    /// Creates a part that is neither a text nor a block.
+    fn part(part: ModelPart) -> Self;
+
+    /// Creates a conditional block.
+    fn conditional(index: usize, body: Box<[Self]>) -> Self;
+
+    /// Creates a repetition block.
+    fn repetition(index: usize, body: Box<[Self]>) -> Self;
+
+    /// Gives their default text to the `#GET n` references of the parts (see `default_parts`).
+    fn with_defaults(parts: Box<[Self]>, defaults: &Map<usize, Box<[u8]>>) -> Box<[Self]>;
+}
+
+impl ParsedPart for ModelPart {
+    /// The text is copied.
+    type Text = Vec<u8>;
+
+    fn push_text(text: &mut Vec<u8>, slice: &[u8], _offset: usize) {
+        text.extend_from_slice(slice);
+    }
+
+    fn flush_text(text: &mut Vec<u8>, parts: &mut Vec<Self>) {
+        if !text.is_empty() {
+            parts.push(ModelPart::Text(core::mem::take(text).into()));
+        }
+    }
+
+    fn part(part: ModelPart) -> Self {
+        part
+    }
+
+    fn conditional(index: usize, body: Box<[Self]>) -> Self {
+        ModelPart::Conditional { index, body }
+    }
+
+    fn repetition(index: usize, body: Box<[Self]>) -> Self {
+        ModelPart::Repetition { index, body }
+    }
+
+    fn with_defaults(parts: Box<[Self]>, defaults: &Map<usize, Box<[u8]>>) -> Box<[Self]> {
+        default_parts(parts, defaults)
+    }
+}
+
+/// Gives their default text to the `#GET n` references of the parts, including inside blocks.
+/// 
+/// A reference that already has a default text keeps it.
+fn default_parts(parts: Box<[ModelPart]>, defaults: &Map<usize, Box<[u8]>>) -> Box<[ModelPart]> {
+    parts.into_vec().into_iter().map(|part| match part {
+        ModelPart::Argument(index) => match defaults.get(&index) {
+            Some(default) => ModelPart::ArgumentOr { index, default: default.clone() },
+            None => ModelPart::Argument(index),
+        },
+        ModelPart::Conditional { index, body } => ModelPart::Conditional { index, body: default_parts(body, defaults) },
+        ModelPart::Repetition { index, body } => ModelPart::Repetition { index, body: default_parts(body, defaults) },
+        part => part,
+    }).collect()
+}
+
+/// Parses the index of an argument from ASCII digits.
+/// 
+/// `usize::MAX` is rejected like an overflow, as the arity of a model using it
+/// would overflow (see `StructureError::IndexOverflow`).
+fn parse_index(digits: &[u8]) -> Option<usize> {
+    core::str::from_utf8(digits).ok()?.parse().ok().filter(|index| *index != usize::MAX)
+}
+
+/// Parses a byte range (`[start..end]`) at the start of the given text.
+/// 
+/// Returns the start, the end and the length of the range.
+fn parse_range(text: &[u8]) -> Option<(usize, usize, usize)> {
+    let parse = |text: &[u8]| -> Option<(usize, usize)> {
+        let digits = text.iter().take_while(|c| c.is_ascii_digit()).count();
+        Some((core::str::from_utf8(&text[..digits]).ok()?.parse().ok()?, digits))
+    };
+    let rest = text.strip_prefix(b"[")?;
+    let (start, start_len) = parse(rest)?;
+    let rest = rest[start_len..].strip_prefix(b"..")?;
+    let (end, end_len) = parse(rest)?;
+    rest[end_len..].strip_prefix(b"]")?;
+    Some((start, end, start_len + end_len + 4))
+}
+
+/// Returns the length of the name (an ASCII letter or `_` followed by ASCII letters,
+/// digits or `_`) at the start of the given text, or zero if there is none.
+fn name_len(text: &[u8]) -> usize {
+    if !matches!(text.first(), Some(c) if c.is_ascii_alphabetic() || *c == b'_') {
+        return 0;
+    }
+    text.iter().take_while(|c| c.is_ascii_alphanumeric() || **c == b'_').count()
+}
+
+impl Default for ModelParser {
+    /// Creates a parser with the default syntax (`#GET 0`).
+    fn default() -> Self {
+        ModelParser::new()
+    }
+}
+
+/// A function giving the content included for each name (see `Model::load_with_resolver`).
+type Resolver<'r, E> = &'r dyn Fn(&str) -> Result<Vec<u8>, E>;
+
+/// An error while parsing the parts of a model (see `ModelParser::parse_parts`).
+enum SourceError<E> {
+    /// The model is invalid.
+    Parse(ParseError),
+
+    /// The resolver returned an error.
+    Resolver(E),
+}
+
+#[cfg(feature = "std")]
+impl From<SourceError<io::Error>> for ReadError<ParseError> {
+    fn from(err: SourceError<io::Error>) -> Self {
+        match err {
+            SourceError::Parse(err) => ReadError::Parse(err),
+            SourceError::Resolver(err) => ReadError::Io(err),
+        }
+    }
+}
+
+/// Returns the first line of the text, with its line terminator.
+fn first_line(text: &[u8]) -> &[u8] {
+    text.iter().position(|byte| *byte == b'\n').map_or(text, |end| &text[..=end])
+}
+
+/// A directive written on its own line in a model.
+enum Directive {
+    /// The start of a conditional block: `#IF n`.
+    If(usize),
+
+    /// The start of a repetition block: `#FOR n`.
+    For(usize),
+
+    /// The end of a block: `#END`.
+    End,
+
+    /// An argument on its own line: `#GETLINE n`.
+    GetLine(usize),
+
+    /// The content included for a name: `#INCLUDE name`.
+    Include(Box<str>),
+
+    /// The default text of a positional argument: `#DEFAULT n text`.
+    Default {
+        /// The index of the argument.
+        index: usize,
+
+        /// The text used if the argument is missing or empty.
+        text: Box<[u8]>,
+    },
+
+    /// A variable set to a positional argument: `#SET name = n`.
+    Set {
+        /// The name of the variable.
+        name: Box<str>,
+
+        /// The index of the argument.
+        index: usize,
+    },
+
+    /// A comment, removed from the model: `#REM ...` or `#//...`.
+    Comment,
+}
+
+// ========================= //
+// ====== FOLDER LOAD ====== //
+// ========================= //
+
+/// The objects loaded from a folder, with the files that could not be loaded.
+/// 
+/// Requires the `std` feature.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct LoadedFolder<T: Readable> {
+    /// The loaded objects, by file name.
+    pub objects: HashMap<String, T>,
+
+    /// The files that could not be loaded, with their error.
+    pub errors: Vec<(PathBuf, ReadError<T::ParseError>)>,
+}
+
+/// Loads objects from folders, in addition to `Readable::load_all`.
+/// 
+/// This trait is implemented for every `Readable` type.
+/// 
+/// Requires the `std` feature.
+#[cfg(feature = "std")]
+pub trait FolderLoad: Readable {
+    /// Get all objects from a folder and its subfolders (one object per file).
+    /// 
+    /// The key of each object is the path of its file relative to the folder,
+    /// with its components separated by `/` (for example `cards/hero.svg`).
+    /// Since two files cannot have the same path, there is no collision between
+    /// the subfolders.
+    /// 
+    /// As with `Readable::load_all`, if a file cannot be loaded or its path is
+    /// not valid UTF-8, it is ignored. If the folder does not exist, an empty
+    /// map is returned.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `folder` - The path to the folder to read from.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use std::fs;
+    /// use svggen::{Model, FolderLoad};
+    /// 
+    /// let folder = std::env::temp_dir().join("svggen_load_folder_recursive");
+    /// # let _ = fs::remove_dir_all(&folder);
+    /// fs::create_dir_all(folder.join("cards")).unwrap();
+    /// fs::create_dir_all(folder.join("badges")).unwrap();
+    /// fs::write(folder.join("base.svg"), "<svg>#GET 0</svg>").unwrap();
+    /// fs::write(folder.join("cards/hero.svg"), "<g>#GET 0</g>").unwrap();
+    /// fs::write(folder.join("badges/hero.svg"), "<text>#GET 0</text>").unwrap();
+    /// 
+    /// let models = Model::load_folder_recursive(&folder);
+    /// 
+    /// let mut names: Vec<&str> = models.keys().map(String::as_str).collect();
+    /// names.sort();
+    /// assert_eq!(names, ["badges/hero.svg", "base.svg", "cards/hero.svg"]);
+    /// assert_eq!(models["cards/hero.svg"], "<g>#GET 0</g>".parse().unwrap());
+    /// # fs::remove_dir_all(&folder).unwrap();
+    /// ```
+    fn load_folder_recursive<P: AsRef<Path>>(folder: P) -> HashMap<String, Self> {
+        let mut map = HashMap::new();
+        let mut folders = vec![(folder.as_ref().to_path_buf(), String::new())];
+        while let Some((folder, prefix)) = folders.pop() {
+            let Ok(entries) = fs::read_dir(folder) else { continue };
+            for entry in entries.flatten() {
+                let Some(name) = entry.file_name().to_str().map(|name| format!("{}{}", prefix, name)) else { continue };
+                if entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+                    folders.push((entry.path(), format!("{}/", name)));
+                } else if let Ok(object) = Self::load_file(entry.path()) {
+                    map.insert(name, object);
+                }
+            }
+        }
+        map
+    }
+
+    /// Get all objects from a folder (one object per file), reporting the files that cannot be loaded.
+    /// 
+    /// The key of each object is its file name, as with `Readable::load_all`.
+    /// The files that cannot be loaded (or whose name is not valid UTF-8) are
+    /// returned with their error, and the other files are still loaded.
+    /// Subfolders are ignored.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `folder` - The path to the folder to read from.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use std::fs;
+    /// use rutil::read::ReadError;
+    /// use svggen::{Model, FolderLoad, ParseErrorKind};
+    /// 
+    /// let folder = std::env::temp_dir().join("svggen_try_load_folder");
+    /// # let _ = fs::remove_dir_all(&folder);
+    /// fs::create_dir_all(&folder).unwrap();
+    /// fs::write(folder.join("valid.svg"), "<svg>#GET 0</svg>").unwrap();
+    /// fs::write(folder.join("invalid.svg"), "<svg>\n#IF 0\n</svg>").unwrap();
+    /// 
+    /// let loaded = Model::try_load_folder(&folder).unwrap();
+    /// 
+    /// assert_eq!(loaded.objects.len(), 1);
+    /// assert!(loaded.objects.contains_key("valid.svg"));
+    /// 
+    /// assert_eq!(loaded.errors.len(), 1);
+    /// assert_eq!(loaded.errors[0].0, folder.join("invalid.svg"));
+    /// assert!(matches!(loaded.errors[0].1, ReadError::Parse(error) if error.kind() == ParseErrorKind::UnclosedBlock));
+    /// # fs::remove_dir_all(&folder).unwrap();
+    /// ```
+    /// 
+    /// # Errors
+    /// 
+    /// * `io::Error` - The folder cannot be read.
+    fn try_load_folder<P: AsRef<Path>>(folder: P) -> io::Result<LoadedFolder<Self>> {
+        let mut map = HashMap::new();
+        let mut errors = Vec::new();
+        for entry in fs::read_dir(folder)? {
+            let entry = entry?;
+            if entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+                continue;
+            }
+            let Some(name) = entry.file_name().to_str().map(str::to_owned) else {
+                let error = io::Error::new(io::ErrorKind::InvalidData, "file name is not valid UTF-8");
+                errors.push((entry.path(), ReadError::Io(error)));
+                continue;
+            };
+            match Self::load_file(entry.path()) {
+                Ok(object) => { map.insert(name, object); }
+                Err(error) => errors.push((entry.path(), error)),
+            }
+        }
+        Ok(LoadedFolder { objects: map, errors })
+    }
+
+    /// Get all objects from the files of a folder that have the given extension.
+    /// 
+    /// The key of each object is the name of its file without the extension
+    /// (for example `hero` for `hero.svg`). The extension is given without the
+    /// dot and is case sensitive. Subfolders are ignored.
+    /// 
+    /// As with `Readable::load_all`, if a file cannot be loaded or its name is
+    /// not valid UTF-8, it is ignored. If the folder does not exist, an empty
+    /// map is returned.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `folder` - The path to the folder to read from.
+    /// * `ext` - The extension of the files to load.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use std::fs;
+    /// use svggen::{Model, FolderLoad};
+    /// 
+    /// let folder = std::env::temp_dir().join("svggen_load_folder_with_extension");
+    /// # let _ = fs::remove_dir_all(&folder);
+    /// fs::create_dir_all(&folder).unwrap();
+    /// fs::write(folder.join("hero.svg"), "<svg>#GET 0</svg>").unwrap();
+    /// fs::write(folder.join("badge.svg"), "<g>#GET 0</g>").unwrap();
+    /// fs::write(folder.join("notes.txt"), "Some notes").unwrap();
+    /// fs::write(folder.join("README"), "No extension").unwrap();
+    /// 
+    /// let models = Model::load_folder_with_extension(&folder, "svg");
+    /// 
+    /// let mut names: Vec<&str> = models.keys().map(String::as_str).collect();
+    /// names.sort();
+    /// assert_eq!(names, ["badge", "hero"]);
+    /// # fs::remove_dir_all(&folder).unwrap();
+    /// ```
+    fn load_folder_with_extension<P: AsRef<Path>>(folder: P, ext: &str) -> HashMap<String, Self> {
+        let mut map = HashMap::new();
+        let Ok(entries) = fs::read_dir(folder) else { return map };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(ext) || !path.is_file() {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else { continue };
+            if let Ok(object) = Self::load_file(&path) {
+                map.insert(name.to_owned(), object);
+            }
+        }
+        map
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Readable> FolderLoad for T {}
+
+/// A set of models, generated by name.
+/// 
+/// Requires the `std` feature.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use std::collections::HashMap;
+/// use svggen::{Model, Argument, TemplateSet, GenerateError};
+/// 
+/// let templates = TemplateSet::from(HashMap::from([
+///     ("card".to_owned(), "<g>#GET 0</g>".parse::<Model>().unwrap()),
+///     ("badge".to_owned(), "<text>#GET 0</text>".parse::<Model>().unwrap()),
+/// ]));
+/// 
+/// assert_eq!(templates.names(), ["badge", "card"]);
+/// assert!(templates.get("card").is_some());
+/// 
+/// let image = templates.render("badge", &[Argument::from("New".as_bytes())]).unwrap();
+/// assert_eq!(image.content(), b"<text>New</text>");
+/// 
+/// let error = templates.render("banner", &[]).unwrap_err();
+/// assert!(matches!(&error, GenerateError::UnknownTemplate { name } if &**name == "banner"));
+/// assert_eq!(error.to_string(), "Unknown template: banner");
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TemplateSet {
+    /// The models, by name.
+    models: HashMap<String, Model>,
+}
+
+#[cfg(feature = "std")]
+impl TemplateSet {
+    /// Creates a new empty set of models.
+    pub fn new() -> Self {
+        TemplateSet::default()
+    }
+
+    /// Adds a model, replacing the model with the same name if there is one.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `name` - The name of the model.
+    /// * `model` - The model to add.
+    pub fn insert(&mut self, name: &str, model: Model) {
+        self.models.insert(name.to_owned(), model);
+    }
+
+    /// Returns the model of the given name, if there is one.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `name` - The name of the model.
+    pub fn get(&self, name: &str) -> Option<&Model> {
+        self.models.get(name)
+    }
+
+    /// Returns the names of the models, sorted.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.models.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Returns the number of models.
+    pub fn len(&self) -> usize {
+        self.models.len()
+    }
+
+    /// Returns `true` if there is no model.
+    pub fn is_empty(&self) -> bool {
+        self.models.is_empty()
+    }
+
+    /// Creates an image from the model of the given name (see `Model::generate`).
+    /// 
+    /// # Arguments
+    /// 
+    /// * `name` - The name of the model.
+    /// * `args` - The arguments to use.
+    /// 
+    /// # Errors
+    /// 
+    /// * `GenerateError::UnknownTemplate` - There is no model with this name.
+    /// * `GenerateError::MissingArgument` - An argument referenced by the model was not given.
+    /// * `GenerateError::DepthExceeded` - The nested models are deeper than `Model::DEFAULT_MAX_DEPTH`.
+    pub fn render(&self, name: &str, args: &[Argument]) -> Result<Image, GenerateError> {
+        self.get(name)
+            .ok_or_else(|| GenerateError::UnknownTemplate { name: name.into() })?
+            .generate(args)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<HashMap<String, Model>> for TemplateSet {
+    /// Creates a set with the given models, by name.
+    fn from(models: HashMap<String, Model>) -> Self {
+        TemplateSet { models }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<TemplateSet> for HashMap<String, Model> {
+    /// Returns the models of the set, by name.
+    fn from(templates: TemplateSet) -> Self {
+        templates.models
     }
 }